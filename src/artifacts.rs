@@ -0,0 +1,131 @@
+use crate::git::Revision;
+use crate::json::Json;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Formats the current UTC time as `YYYYMMDD-HHMMSS`
+pub fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, time) = (secs / 86400, secs % 86400);
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    #[allow(clippy::cast_possible_wrap)] // Correctness: days since epoch fit in an i64
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Directory collecting everything produced by a single run.
+///
+/// Every file written through it gets listed in the `manifest.json` written by
+/// [`RunArtifacts::finish`].
+pub struct RunArtifacts {
+    dir: PathBuf,
+    files: Vec<(String, &'static str)>,
+}
+
+impl RunArtifacts {
+    pub const CRASHES_DIR: &'static str = "crashes";
+    pub const REPLAYS_DIR: &'static str = "replays";
+    pub const INTERESTING_DIR: &'static str = "interesting";
+
+    /// Creates a new timestamped run directory inside `base`
+    pub fn create(base: &Path) -> io::Result<Self> {
+        let stamp = timestamp();
+        let mut dir = base.join(format!("run-{stamp}"));
+        let mut suffix = 1;
+        while dir.exists() {
+            suffix += 1;
+            dir = base.join(format!("run-{stamp}-{suffix}"));
+        }
+        fs::create_dir_all(dir.join(Self::CRASHES_DIR))?;
+
+        Ok(Self {
+            dir,
+            files: Vec::new(),
+        })
+    }
+
+    pub fn crashes_dir(&self) -> PathBuf {
+        self.dir.join(Self::CRASHES_DIR)
+    }
+
+    pub fn replays_dir(&self) -> PathBuf {
+        self.dir.join(Self::REPLAYS_DIR)
+    }
+
+    pub fn interesting_dir(&self) -> PathBuf {
+        self.dir.join(Self::INTERESTING_DIR)
+    }
+
+    pub fn write(
+        &mut self,
+        name: &str,
+        description: &'static str,
+        contents: impl AsRef<[u8]>,
+    ) -> io::Result<()> {
+        fs::write(self.dir.join(name), contents)?;
+        self.files.push((name.to_owned(), description));
+        Ok(())
+    }
+
+    /// Writes the manifest describing the contents of the run directory, the game revision they
+    /// come from and the `metadata` of the run
+    pub fn finish(self, revision: Option<&Revision>, metadata: Json) -> io::Result<PathBuf> {
+        let count_files = |dir: PathBuf| {
+            fs::read_dir(dir).map_or(0, |entries| {
+                u32::try_from(entries.count()).unwrap_or(u32::MAX)
+            })
+        };
+
+        let files = self
+            .files
+            .iter()
+            .map(|(name, description)| {
+                let bytes = fs::metadata(self.dir.join(name)).map_or(0, |m| m.len());
+                #[allow(clippy::cast_precision_loss)] // Correctness: files won't reach 2^52 bytes
+                Json::object([
+                    ("path", name.as_str().into()),
+                    ("description", (*description).into()),
+                    ("bytes", Json::Number(bytes as f64)),
+                ])
+            })
+            .collect();
+
+        let manifest = Json::object([
+            ("tester_version", env!("CARGO_PKG_VERSION").into()),
+            ("finished_at", timestamp().into()),
+            ("git", revision.map_or(Json::Null, Revision::to_json)),
+            ("metadata", metadata),
+            ("files", Json::Array(files)),
+            ("crash_dumps", count_files(self.crashes_dir()).into()),
+            ("replays", count_files(self.replays_dir()).into()),
+            (
+                "interesting_games",
+                count_files(self.interesting_dir()).into(),
+            ),
+        ]);
+        fs::write(self.dir.join("manifest.json"), format!("{manifest}\n"))?;
+
+        Ok(self.dir)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::errors::AppError;
+use crate::results::TestResults;
+use crate::runner::GameRunner;
+use crate::timing::{CpuStats, DurationStats};
+use crate::TestConfig;
+
+use color_eyre::eyre::Result;
+use rayon::prelude::*;
+use std::{num::NonZeroU32, time::Instant};
+
+/// Plays the configured seed set `runs` times, reporting the throughput of every run and how
+/// much it varied between them
+pub fn run(config: &TestConfig, runs: NonZeroU32) -> Result<()> {
+    let runner = GameRunner::new(config, None, None)?;
+    let last_seed = config
+        .seed
+        .checked_add(config.instances.get() - 1)
+        .ok_or(AppError::SeedRangeOutOfBounds)?;
+    let seeds = config.seed..=last_seed;
+    let jobs = rayon::current_num_threads();
+
+    println!(
+        "Benchmarking {} seeds, {runs} runs with {jobs} jobs",
+        config.instances
+    );
+
+    let mut throughputs = Vec::new();
+    for run in 1..=runs.get() {
+        let start = Instant::now();
+        let results = seeds
+            .clone()
+            .into_par_iter()
+            .map(|seed| runner.run(seed).map(TestResults::from))
+            .reduce(|| Ok(TestResults::default()), |a, b| Ok(a?.merge(b?)))?;
+        let elapsed = start.elapsed();
+
+        let throughput = f64::from(config.instances.get()) / elapsed.as_secs_f64();
+        throughputs.push(throughput);
+
+        let mut parts = vec![format!("{elapsed:.2?}"), format!("{throughput:.2} games/s")];
+        if let Some(durations) = DurationStats::compute(&results) {
+            parts.push(format!("{:.2?} per game", durations.average));
+        }
+        if let Some(cpu) = CpuStats::compute(&results) {
+            parts.push(format!("{:.2?} CPU per game", cpu.average));
+        }
+        if !results.failed_seeds.is_empty() {
+            parts.push(format!("{} crashed", results.failed_seeds.len()));
+        }
+        println!("=> Run {run}: {}", parts.join(", "));
+    }
+
+    let count = f64::from(runs.get());
+    let mean = throughputs.iter().sum::<f64>() / count;
+    let variance = throughputs.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / count;
+    let std_dev = variance.sqrt();
+
+    println!();
+    println!(
+        "Throughput: {mean:.2} games/s in average, ±{std_dev:.2} ({:.1}% run-to-run variation)",
+        std_dev * 100. / mean
+    );
+    println!(
+        "If the time per game is well above its CPU time, the games are competing for the CPU \
+         and the players' time limits are distorted: try fewer --jobs"
+    );
+
+    Ok(())
+}
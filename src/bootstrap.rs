@@ -0,0 +1,43 @@
+//! Bootstrap confidence intervals, which don't assume the scores are normally distributed.
+
+use crate::results::TestResults;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// Resamples drawn for every interval
+const RESAMPLES: u32 = 1000;
+/// Confidence level of the intervals
+pub const CONFIDENCE: f64 = 0.95;
+
+/// Confidence interval of the average score of the player in `seats`, from resampling the games
+/// with replacement. The resampling is seeded, so the same results always give the same interval
+pub fn score_interval(results: &TestResults, seats: &[usize]) -> Option<(f64, f64)> {
+    let scores: Vec<f64> = results
+        .games
+        .iter()
+        .filter_map(|g| g.points)
+        .map(|points| seats.iter().map(|&s| f64::from(points[s])).sum::<f64>())
+        .collect();
+    if scores.len() < 2 {
+        return None;
+    }
+
+    let mut rng = SmallRng::seed_from_u64(0);
+    let count = f64::from(u32::try_from(scores.len()).unwrap_or(u32::MAX))
+        * f64::from(u32::try_from(seats.len()).unwrap_or(4));
+    let mut means: Vec<f64> = (0..RESAMPLES)
+        .map(|_| {
+            let total: f64 = (0..scores.len())
+                .map(|_| scores[rng.gen_range(0..scores.len())])
+                .sum();
+            total / count
+        })
+        .collect();
+    means.sort_unstable_by(f64::total_cmp);
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    // Correctness: The quantiles are between 0 and RESAMPLES
+    let quantile = |q: f64| means[((q * f64::from(RESAMPLES)) as usize).min(means.len() - 1)];
+    let tail = (1. - CONFIDENCE) / 2.;
+    Some((quantile(tail), quantile(1. - tail)))
+}
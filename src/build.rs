@@ -0,0 +1,72 @@
+use crate::errors::AppError;
+
+use color_eyre::eyre::Result;
+use std::{fs, path::Path, process::Command};
+use tracing::info;
+
+/// Name the player registers itself with, from its `#define PLAYER_NAME` line
+fn player_name(source: &str) -> Option<&str> {
+    source.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        (words.next() == Some("#define") && words.next() == Some("PLAYER_NAME"))
+            .then(|| words.next())
+            .flatten()
+    })
+}
+
+/// Copies the player sources into the game folder, rebuilds the game and checks that every
+/// player got registered in `game`
+pub fn install_players(sources: &[impl AsRef<Path>], game: &Path) -> Result<()> {
+    if sources.is_empty() {
+        return Ok(());
+    }
+
+    let mut names = Vec::new();
+    for source in sources {
+        let source = source.as_ref();
+        let file_name = source
+            .file_name()
+            .ok_or_else(|| AppError::MissingFile(source.to_owned()))?;
+        let contents =
+            fs::read_to_string(source).map_err(|_| AppError::MissingFile(source.to_owned()))?;
+        let name = player_name(&contents)
+            .ok_or_else(|| AppError::MissingPlayerName(source.to_owned()))?
+            .to_owned();
+
+        // Sources already in the game folder don't need copying
+        let destination = Path::new(file_name);
+        if fs::canonicalize(source).ok() != fs::canonicalize(destination).ok() {
+            fs::copy(source, destination)?;
+        }
+        info!(name, source = %source.display(), "Installed player");
+        names.push(name);
+    }
+
+    make()?;
+    check_registered(game, &names)
+}
+
+/// Checks that `game --list` lists every one of `names`
+pub fn check_registered(game: &Path, names: &[String]) -> Result<()> {
+    let output = Command::new(game).arg("--list").output()?;
+    let listed = String::from_utf8_lossy(&output.stdout) + String::from_utf8_lossy(&output.stderr);
+    for name in names {
+        if !listed.split_whitespace().any(|word| word == name) {
+            return Err(AppError::PlayerNotRegistered(name.clone()).into());
+        }
+    }
+    Ok(())
+}
+
+/// Rebuilds the game with `make`
+pub fn make() -> Result<()> {
+    println!("Building the game...");
+    let output = Command::new("make").output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<_> = stderr.lines().rev().take(20).collect();
+        let tail: Vec<_> = tail.into_iter().rev().collect();
+        return Err(AppError::BuildFailed(tail.join("\n")).into());
+    }
+    Ok(())
+}
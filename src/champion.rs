@@ -0,0 +1,131 @@
+//! `--champion`: a frozen build of the game with the best known version of the focal player,
+//! played on the same seeds as the current build in every run to tell whether it got better.
+
+use crate::results::TestResults;
+
+use color_eyre::eyre::Result;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::Path,
+};
+
+/// Points of the player on every seed that didn't crash, summed over the repetitions
+fn points_by_seed(results: &TestResults, seat: usize) -> BTreeMap<u32, u64> {
+    let mut ret = BTreeMap::new();
+    for game in &results.games {
+        if let Some(points) = game.points {
+            *ret.entry(game.seed).or_default() += u64::from(points[seat]);
+        }
+    }
+    ret
+}
+
+/// Average points and win rate of the player
+fn stats(results: &TestResults, seat: usize) -> (f64, f64) {
+    let games = f64::from(results.ok_games().max(1));
+    let player = &results.player_results[seat];
+    (
+        f64::from(player.total_points) / games,
+        f64::from(player.total_wins) * 100. / games,
+    )
+}
+
+/// The focal player of the current build against the one of the champion
+pub struct Comparison {
+    current: (f64, f64),
+    champion: (f64, f64),
+    /// Seeds both builds played where the current one scored more, the same and less
+    better: usize,
+    same: usize,
+    worse: usize,
+}
+
+impl Comparison {
+    pub fn new(current: &TestResults, champion: &TestResults, seat: usize) -> Self {
+        let champion_points = points_by_seed(champion, seat);
+        let (mut better, mut same, mut worse) = (0, 0, 0);
+        for (seed, points) in points_by_seed(current, seat) {
+            match champion_points.get(&seed).map(|c| points.cmp(c)) {
+                Some(std::cmp::Ordering::Greater) => better += 1,
+                Some(std::cmp::Ordering::Equal) => same += 1,
+                Some(std::cmp::Ordering::Less) => worse += 1,
+                None => {}
+            }
+        }
+        Self {
+            current: stats(current, seat),
+            champion: stats(champion, seat),
+            better,
+            same,
+            worse,
+        }
+    }
+
+    /// Whether the current build scores more in average, and on more seeds than it scores less
+    pub fn beats(&self) -> bool {
+        self.current.0 > self.champion.0 && self.better > self.worse
+    }
+
+    pub fn write(&self, out: &mut impl Write, player: &str, champion: &Path) -> io::Result<()> {
+        let ((score, wr), (old_score, old_wr)) = (self.current, self.champion);
+        writeln!(
+            out,
+            "Against the champion {} over {} common seeds:",
+            champion.display(),
+            self.better + self.same + self.worse
+        )?;
+        writeln!(
+            out,
+            "=> {player}: {score:.1} points in average ({:+.1}), {wr:.1}% WR ({:+.1})",
+            score - old_score,
+            wr - old_wr
+        )?;
+        writeln!(
+            out,
+            "=> Scored more on {} seeds, the same on {} and less on {}",
+            self.better, self.same, self.worse
+        )?;
+        if self.beats() {
+            writeln!(out, "=> The current build beats the champion")
+        } else {
+            writeln!(out, "=> The current build doesn't beat the champion")
+        }
+    }
+}
+
+/// Makes `game` the new champion, asking first on `out` unless `promote` is set. Without a
+/// terminal to ask in, it only tells how to promote it
+pub fn offer_promotion(
+    out: &mut impl Write,
+    game: &Path,
+    champion: &Path,
+    promote: bool,
+) -> Result<()> {
+    if !promote {
+        if !io::stdin().is_terminal() {
+            writeln!(out, "Run again with --promote to make it the new champion")?;
+            return Ok(());
+        }
+        write!(out, "Make the current build the new champion? [y/N] ")?;
+        out.flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+
+    // Copied beside it and then renamed, so the champion is never left half written
+    let new = champion.with_extension("new");
+    fs::copy(game, &new)?;
+    fs::rename(&new, champion)?;
+    writeln!(
+        out,
+        "{} is the new champion, copied to {}",
+        game.display(),
+        champion.display()
+    )?;
+    Ok(())
+}
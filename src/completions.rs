@@ -0,0 +1,166 @@
+//! Shell completion scripts, generated from the clap definition of the command line.
+
+use clap::{Arg, Command, ValueEnum};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Flags of a command, as the shell sees them
+fn flags(command: &Command) -> impl Iterator<Item = &Arg> {
+    command
+        .get_arguments()
+        .filter(|a| !a.is_positional() && !a.is_hide_set())
+}
+
+fn spellings(arg: &Arg) -> Vec<String> {
+    let mut spellings: Vec<_> = arg
+        .get_short()
+        .map(|s| format!("-{s}"))
+        .into_iter()
+        .collect();
+    spellings.extend(arg.get_long().map(|l| format!("--{l}")));
+    spellings
+}
+
+fn possible_values(arg: &Arg) -> Vec<String> {
+    arg.get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_owned())
+        .collect()
+}
+
+/// First line of the help of an argument or command
+fn summary(help: Option<&clap::builder::StyledStr>) -> String {
+    help.map(ToString::to_string)
+        .and_then(|h| h.lines().next().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+fn bash(command: &Command) -> String {
+    let name = command.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let words = |command: &Command| {
+        let mut words: Vec<_> = command
+            .get_subcommands()
+            .map(|c| c.get_name().to_owned())
+            .collect();
+        words.extend(flags(command).flat_map(spellings));
+        words.join(" ")
+    };
+
+    let mut lines = vec![
+        format!("{function}() {{"),
+        r#"    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}" cmd="" word"#
+            .to_owned(),
+        r#"    for word in "${COMP_WORDS[@]:1:COMP_CWORD-1}"; do"#.to_owned(),
+        "        case \"$word\" in".to_owned(),
+    ];
+    let subcommands: Vec<_> = command.get_subcommands().map(Command::get_name).collect();
+    lines.push(format!(
+        "            {}) cmd=\"$word\"; break ;;",
+        subcommands.join("|")
+    ));
+    lines.extend(["        esac".to_owned(), "    done".to_owned()]);
+
+    // Options with a fixed set of values
+    lines.push("    case \"$prev\" in".to_owned());
+    let mut seen = Vec::new();
+    for arg in command
+        .get_subcommands()
+        .flat_map(flags)
+        .chain(flags(command))
+    {
+        let values = possible_values(arg);
+        let spellings = spellings(arg);
+        if values.is_empty() || !arg.get_action().takes_values() || seen.contains(&spellings) {
+            continue;
+        }
+        lines.push(format!(
+            "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;",
+            spellings.join("|"),
+            values.join(" ")
+        ));
+        seen.push(spellings);
+    }
+    lines.push("    esac".to_owned());
+
+    lines.push("    local words".to_owned());
+    lines.push("    case \"$cmd\" in".to_owned());
+    for subcommand in command.get_subcommands() {
+        lines.push(format!(
+            "        {}) words=\"{}\" ;;",
+            subcommand.get_name(),
+            words(subcommand)
+        ));
+    }
+    lines.push(format!("        *) words=\"{}\" ;;", words(command)));
+    lines.extend([
+        "    esac".to_owned(),
+        "    if [[ \"$cur\" == -* || -z \"$cmd\" ]]; then".to_owned(),
+        "        COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))".to_owned(),
+        "    fi".to_owned(),
+        "}".to_owned(),
+        format!("complete -o default -F {function} {name}"),
+    ]);
+    lines.join("\n") + "\n"
+}
+
+fn fish(command: &Command) -> String {
+    let name = command.get_name();
+    let quote = |s: &str| format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"));
+    let complete = |condition: &str, arg: &Arg| {
+        let mut parts = vec![format!("complete -c {name} -n {}", quote(condition))];
+        if let Some(short) = arg.get_short() {
+            parts.push(format!("-s {short}"));
+        }
+        if let Some(long) = arg.get_long() {
+            parts.push(format!("-l {long}"));
+        }
+        if arg.get_action().takes_values() {
+            parts.push("-r".to_owned());
+            let values = possible_values(arg);
+            if !values.is_empty() {
+                parts.push(format!("-f -a {}", quote(&values.join(" "))));
+            }
+        }
+        let help = summary(arg.get_help());
+        if !help.is_empty() {
+            parts.push(format!("-d {}", quote(&help)));
+        }
+        parts.join(" ")
+    };
+
+    let mut lines = Vec::new();
+    for arg in flags(command) {
+        lines.push(complete("__fish_use_subcommand", arg));
+    }
+    for subcommand in command.get_subcommands() {
+        let sub = subcommand.get_name();
+        lines.push(format!(
+            "complete -c {name} -n __fish_use_subcommand -f -a {sub} -d {}",
+            quote(&summary(subcommand.get_about()))
+        ));
+        let condition = format!("__fish_seen_subcommand_from {sub}");
+        for arg in flags(subcommand) {
+            lines.push(complete(&condition, arg));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Completion script of `command` for `shell`
+pub fn generate(mut command: Command, shell: Shell) -> String {
+    // Propagates the global options and adds the help flags
+    command.build();
+    match shell {
+        Shell::Bash => bash(&command),
+        // zsh can run bash completions, which avoids keeping a second generator in sync
+        Shell::Zsh => "autoload -U +X bashcompinit && bashcompinit\n".to_owned() + &bash(&command),
+        Shell::Fish => fish(&command),
+    }
+}
@@ -0,0 +1,277 @@
+//! Project configuration file, giving defaults to the command line options.
+//!
+//! Only a subset of TOML is understood: `key = value` lines with strings, integers, floats,
+//! booleans and single-line arrays of them, and `#` comments. Keys are the option names, with
+//! `-` or `_`, and `players` sets the four player names at once.
+//!
+//! Presets bundle options under a name, picked with `--preset`: a `[preset.NAME]` table holds
+//! any option, and `preset.NAME = [...]` is a shorthand for just the players.
+
+use crate::errors::AppError;
+
+use clap::Command;
+use color_eyre::eyre::Result;
+use std::{fs, path::Path, slice};
+
+/// Configuration file looked for in the current directory
+pub const FILE: &str = "eda-tester.toml";
+
+#[derive(Debug)]
+enum Value {
+    Scalar(String),
+    Array(Vec<String>),
+}
+
+/// Splits `s` at the first `delimiter` outside of a string
+fn split_unquoted(s: &str, delimiter: char) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => return Some((&s[..i], &s[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_scalar(s: &str) -> Option<String> {
+    let s = s.trim();
+    if let Some(s) = s.strip_prefix('"') {
+        let s = s.strip_suffix('"')?;
+        let mut value = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            value.push(match c {
+                '\\' => match chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    c @ ('\\' | '"') => c,
+                    _ => return None,
+                },
+                '"' => return None,
+                c => c,
+            });
+        }
+        return Some(value);
+    }
+    if let Some(s) = s.strip_prefix('\'') {
+        return s.strip_suffix('\'').map(str::to_owned);
+    }
+
+    let number = s.replace('_', "");
+    (s == "true" || s == "false" || number.parse::<f64>().is_ok()).then_some(number)
+}
+
+fn parse_value(s: &str) -> Option<Value> {
+    let s = s.trim();
+    let Some(items) = s.strip_prefix('[') else {
+        return parse_scalar(s).map(Value::Scalar);
+    };
+    let mut items = items.strip_suffix(']')?.trim();
+
+    let mut values = Vec::new();
+    while !items.is_empty() {
+        let (item, rest) = split_unquoted(items, ',').unwrap_or((items, ""));
+        values.push(parse_scalar(item)?);
+        items = rest.trim();
+    }
+    Some(Value::Array(values))
+}
+
+type Entries = Vec<(String, Value)>;
+
+/// Splits the file into the top-level entries and the presets, given as `[preset.NAME]` tables
+/// or as a `preset.NAME` lineup
+fn parse(contents: &str) -> Result<(Entries, Vec<(String, Entries)>), String> {
+    let mut entries = Vec::new();
+    let mut presets: Vec<(String, Entries)> = Vec::new();
+    let mut in_preset = false;
+    for (number, line) in contents.lines().enumerate() {
+        let line = split_unquoted(line, '#')
+            .map_or(line, |(line, _)| line)
+            .trim();
+        if line.is_empty() {
+            continue;
+        }
+        let err = || format!("line {}: `{line}`", number + 1);
+
+        if let Some(table) = line.strip_prefix('[') {
+            let name = table
+                .strip_suffix(']')
+                .and_then(|t| t.trim().strip_prefix("preset."))
+                .filter(|name| !name.is_empty())
+                .ok_or_else(err)?;
+            presets.push((name.to_owned(), Vec::new()));
+            in_preset = true;
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').ok_or_else(err)?;
+        let key = key.trim().replace('-', "_");
+        let value = parse_value(value).ok_or_else(err)?;
+        if key.is_empty() {
+            return Err(err());
+        }
+        match key.strip_prefix("preset.") {
+            Some(name) if !in_preset => {
+                presets.push((name.to_owned(), vec![("players".to_owned(), value)]));
+            }
+            Some(_) => return Err(err()),
+            None if in_preset => presets.last_mut().ok_or_else(err)?.1.push((key, value)),
+            None => entries.push((key, value)),
+        }
+    }
+    Ok((entries, presets))
+}
+
+/// Sets the default of the option `id` in `command` and all its subcommands, returning whether
+/// any has it
+fn set_default(command: Command, id: &str, values: &[String]) -> (Command, bool) {
+    let has_arg = command.get_arguments().any(|a| a.get_id() == id);
+    let mut command = if has_arg {
+        command.mut_arg(id, |arg| {
+            arg.default_values(values.iter().cloned()).required(false)
+        })
+    } else {
+        command
+    };
+
+    let mut found = has_arg;
+    let subcommands: Vec<_> = command
+        .get_subcommands()
+        .map(|c| c.get_name().to_owned())
+        .collect();
+    for name in subcommands {
+        command = command.mut_subcommand(name, |sub| {
+            let (sub, has_arg) = set_default(sub, id, values);
+            found |= has_arg;
+            sub
+        });
+    }
+    (command, found)
+}
+
+/// Fills the four seats with `player`, for `--mirror`
+pub fn mirror(mut command: Command, player: &str) -> Command {
+    for i in 1..=4 {
+        command = set_default(command, &format!("player{i}"), &[player.to_owned()]).0;
+    }
+    command
+}
+
+/// Gives the seats after the first one a default from `players`, in turn, so the seats left
+/// out on the command line get filled with them
+pub fn fill(mut command: Command, players: &[String]) -> Command {
+    for (i, player) in (2..=4).zip(players.iter().cycle()) {
+        command = set_default(command, &format!("player{i}"), slice::from_ref(player)).0;
+    }
+    command
+}
+
+/// Options picked on the command line that give defaults to other arguments
+pub struct Lookahead {
+    /// Presets picked with `--preset`. The first one is applied to the actual parsing
+    pub presets: Vec<String>,
+    /// Player of `--mirror`
+    pub mirror: Option<String>,
+    /// Players of `--fill`
+    pub fill: Vec<String>,
+}
+
+/// Defaults for the command line options, read from the configuration file in the current
+/// directory
+#[derive(Default)]
+pub struct ProjectConfig {
+    defaults: Entries,
+    /// Named lineups and option bundles, applied on top of the defaults
+    presets: Vec<(String, Entries)>,
+}
+
+impl ProjectConfig {
+    /// Reads the configuration file, if there's one
+    pub fn load() -> Result<Self> {
+        let path = Path::new(FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        let (defaults, presets) =
+            parse(&contents).map_err(|line| AppError::InvalidConfig(path.to_owned(), line))?;
+        Ok(Self { defaults, presets })
+    }
+
+    /// Reads the options that provide required arguments, before the actual parsing
+    pub fn lookahead(&self, command: Command) -> Result<Lookahead> {
+        let mut matches = &self.apply(command, None)?.ignore_errors(true).get_matches();
+        while let Some((_, sub)) = matches.subcommand() {
+            matches = sub;
+        }
+        Ok(Lookahead {
+            presets: matches
+                .try_get_many::<String>("preset")
+                .ok()
+                .flatten()
+                .map(|presets| presets.cloned().collect())
+                .unwrap_or_default(),
+            mirror: matches
+                .try_get_one::<String>("mirror")
+                .ok()
+                .flatten()
+                .cloned(),
+            fill: matches
+                .try_get_many::<String>("fill")
+                .ok()
+                .flatten()
+                .map(|players| players.cloned().collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Sets the defaults of `command`, and those of `preset` if given
+    pub fn apply(&self, mut command: Command, preset: Option<&str>) -> Result<Command> {
+        let preset = match preset {
+            Some(name) => {
+                &self
+                    .presets
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .ok_or_else(|| AppError::UnknownPreset(name.to_owned()))?
+                    .1
+            }
+            None => &Vec::new(),
+        };
+        let path = Path::new(FILE);
+
+        for (key, value) in self.defaults.iter().chain(preset) {
+            let defaults = match (key.as_str(), value) {
+                ("players", Value::Array(names)) if names.len() == 4 => names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| (format!("player{}", i + 1), vec![name.clone()]))
+                    .collect(),
+                ("players", _) => {
+                    return Err(AppError::InvalidConfig(
+                        path.to_owned(),
+                        "`players` needs 4 names".to_owned(),
+                    )
+                    .into())
+                }
+                (_, Value::Scalar(value)) => vec![(key.clone(), vec![value.clone()])],
+                (_, Value::Array(values)) => vec![(key.clone(), values.clone())],
+            };
+
+            for (id, values) in defaults {
+                let found;
+                (command, found) = set_default(command, &id, &values);
+                if !found {
+                    return Err(AppError::UnknownConfigKey(path.to_owned(), id).into());
+                }
+            }
+        }
+        Ok(command)
+    }
+}
@@ -0,0 +1,79 @@
+//! `--cpu-budget`: the CPU time the judge lets a game use, scaled to this machine, to find the
+//! games that would go over it on the judge even though they finished here. The players share the
+//! game process, so only the CPU time of the whole game can be measured from outside of it, not
+//! the one of every player or turn.
+
+use crate::results::TestResults;
+use crate::usage::ResourceUsage;
+
+use std::{process::Command, time::Duration};
+
+#[derive(Clone, Copy, Debug)]
+pub struct CpuBudget {
+    /// CPU time a game can use on the judge
+    pub budget: Duration,
+    /// How many times slower the judge is than this machine
+    pub slowdown: f64,
+    /// Kill the games once they go over the budget, instead of only reporting them
+    pub enforce: bool,
+}
+
+impl CpuBudget {
+    /// CPU time a game can use on this machine
+    pub fn local(&self) -> Duration {
+        self.budget.div_f64(self.slowdown)
+    }
+
+    /// Games over the budget and the CPU time they would take on the judge, the most first
+    pub fn over(&self, results: &TestResults) -> Vec<(u32, Duration)> {
+        let mut ret: Vec<_> = results
+            .games
+            .iter()
+            .filter_map(|g| Some((g.seed, g.usage.cpu?.total().mul_f64(self.slowdown))))
+            .filter(|&(_, judge)| judge > self.budget)
+            .collect();
+        ret.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ret
+    }
+
+    /// CPU seconds the games are limited to when enforcing the budget, rounded up
+    fn limit(&self) -> u64 {
+        let local = self.local();
+        (local.as_secs() + u64::from(local.subsec_nanos() > 0)).max(1)
+    }
+
+    /// Whether the budget was enforced on a game and it used all of it, getting it killed
+    pub fn used_up(&self, usage: &ResourceUsage) -> bool {
+        self.enforce
+            && usage
+                .cpu
+                .is_some_and(|cpu| cpu.total() >= Duration::from_secs(self.limit()))
+    }
+
+    /// Limits the CPU time of the game `command` starts, which gets `SIGXCPU` once over the
+    /// budget. The limit is in whole seconds, rounded up
+    #[cfg(unix)]
+    pub fn enforce_on(&self, command: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        let seconds = libc::rlim_t::try_from(self.limit()).unwrap_or(libc::RLIM_INFINITY);
+        let limit = libc::rlimit {
+            rlim_cur: seconds,
+            // Killed for good if it ignores SIGXCPU
+            rlim_max: seconds.saturating_add(1),
+        };
+        // SAFETY: setrlimit is async-signal-safe, and the closure touches no memory of the parent
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setrlimit(libc::RLIMIT_CPU, &raw const limit) == 0 {
+                    Ok(())
+                } else {
+                    Err(std::io::Error::last_os_error())
+                }
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn enforce_on(&self, _command: &mut Command) {}
+}
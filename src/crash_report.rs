@@ -0,0 +1,238 @@
+//! `crash_report.json`: the failures of a run grouped by kind, with the seeds, commands and saved
+//! files needed to look into them, for tooling and for triaging without the terminal output.
+
+use crate::json::Json;
+use crate::metadata;
+use crate::parser::PlayerStatus;
+use crate::plan;
+use crate::results::TestResults;
+use crate::runner::CrashKind;
+use crate::TestConfig;
+
+use itertools::Itertools;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub const FILE_NAME: &str = "crash_report.json";
+
+/// Whether anything went wrong in the run
+pub fn has_failures(config: &TestConfig, results: &TestResults) -> bool {
+    !results.failed_seeds.is_empty()
+        || config
+            .cpu_budget
+            .is_some_and(|budget| !budget.over(results).is_empty())
+        || !results.unstarted_seeds.is_empty()
+        || !results.parse_error_seeds.is_empty()
+        || results
+            .player_results
+            .iter()
+            .any(|r| r.timeouts > 0 || r.disqualifications > 0)
+}
+
+fn count(len: usize) -> Json {
+    u32::try_from(len).unwrap_or(u32::MAX).into()
+}
+
+/// Each seed once, in order, as a seed played several times is in the lists as many times
+fn distinct(seeds: &[u32]) -> Vec<u32> {
+    seeds.iter().copied().sorted().dedup().collect()
+}
+
+fn seeds(seeds: &[u32]) -> Json {
+    Json::Array(seeds.iter().map(|&s| s.into()).collect())
+}
+
+/// Path of a file saved for a seed, which may have been compressed since
+fn saved(dir: Option<&Path>, name: &str) -> Option<PathBuf> {
+    let dir = dir?;
+    ["", ".gz", ".zst"]
+        .iter()
+        .map(|extension| dir.join(format!("{name}{extension}")))
+        .find(|path| path.exists())
+}
+
+fn paths(paths: impl Iterator<Item = PathBuf>) -> Json {
+    Json::Array(
+        paths
+            .map(|path| path.to_string_lossy().into_owned().into())
+            .collect(),
+    )
+}
+
+/// Seeds where each player ended with `status`, for the players it happened to
+fn by_player(config: &TestConfig, results: &TestResults, status: PlayerStatus) -> Json {
+    let players = (0..4)
+        .filter_map(|seat| {
+            let seeds_with: Vec<u32> = results
+                .games
+                .iter()
+                .filter(|g| g.status[seat] == status)
+                .map(|g| g.seed)
+                .sorted()
+                .dedup()
+                .collect();
+            (!seeds_with.is_empty()).then(|| {
+                Json::object([
+                    ("player", config.players[seat].as_string().into()),
+                    ("count", count(seeds_with.len())),
+                    ("seeds", seeds(&seeds_with)),
+                ])
+            })
+        })
+        .collect();
+    Json::Array(players)
+}
+
+/// How many games crashed of every kind, like segfaults, aborts or timeouts
+fn crash_kinds(results: &TestResults) -> Json {
+    Json::Array(
+        results
+            .crash_kinds()
+            .into_iter()
+            .map(|(kind, games)| {
+                Json::object([
+                    ("kind", kind.id().into()),
+                    ("description", kind.to_string().into()),
+                    ("count", count(games)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Games over the `--cpu-budget`, with the CPU time they would take on the judge
+fn over_cpu_budget(config: &TestConfig, results: &TestResults) -> Json {
+    let Some(budget) = &config.cpu_budget else {
+        return Json::Null;
+    };
+    let millis = |d: Duration| u32::try_from(d.as_millis()).unwrap_or(u32::MAX).into();
+    let over = budget.over(results);
+    Json::object([
+        ("budget_ms", millis(budget.budget)),
+        ("judge_slowdown", budget.slowdown.into()),
+        ("count", count(over.len())),
+        (
+            "games",
+            Json::Array(
+                over.into_iter()
+                    .map(|(seed, cpu)| {
+                        Json::object([("seed", seed.into()), ("judge_cpu_ms", millis(cpu))])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+/// Report of the failures of a run, with the replays and crash dumps found in their folders
+pub fn report(
+    config: &TestConfig,
+    results: &TestResults,
+    replays: Option<&Path>,
+    crash_dumps: Option<&Path>,
+) -> Json {
+    let command = plan::command_line(config);
+
+    let mut signatures: BTreeMap<(&str, CrashKind), Vec<u32>> = BTreeMap::new();
+    for game in &results.games {
+        if let Some(crash) = game.signature.as_deref().zip(game.crash_kind) {
+            signatures.entry(crash).or_default().push(game.seed);
+        }
+    }
+    let crashes = signatures
+        .into_iter()
+        .map(|(crash, mut crashed)| {
+            crashed.sort_unstable();
+            crashed.dedup();
+            (crash, crashed)
+        })
+        // The most common causes first
+        .sorted_by_key(|(_, crashed)| std::cmp::Reverse(crashed.len()))
+        .map(|((signature, kind), crashed)| {
+            Json::object([
+                ("kind", kind.id().into()),
+                ("signature", signature.into()),
+                ("count", count(crashed.len())),
+                ("seeds", seeds(&crashed)),
+                (
+                    "reproduce",
+                    command.replace("SEED", &crashed[0].to_string()).into(),
+                ),
+                (
+                    "crash_dumps",
+                    paths(
+                        crashed
+                            .iter()
+                            .filter_map(|s| saved(crash_dumps, &format!("{s}.stderr"))),
+                    ),
+                ),
+                (
+                    "replays",
+                    paths(
+                        crashed
+                            .iter()
+                            .filter_map(|s| saved(replays, &format!("{s}.res"))),
+                    ),
+                ),
+            ])
+        })
+        .collect();
+
+    let unstarted = distinct(&results.unstarted_seeds);
+    let parse_errors = distinct(&results.parse_error_seeds);
+    Json::object([
+        (
+            "players",
+            Json::Array(
+                config
+                    .players
+                    .iter()
+                    .map(|p| p.as_string().into())
+                    .collect(),
+            ),
+        ),
+        ("command", command.as_str().into()),
+        ("settings_file", config.settings_file.as_str().into()),
+        ("metadata", metadata::collect(config)),
+        (
+            "games",
+            count(results.games.len() + parse_errors.len() + unstarted.len()),
+        ),
+        ("crash_kinds", crash_kinds(results)),
+        ("crashes", Json::Array(crashes)),
+        ("over_cpu_budget", over_cpu_budget(config, results)),
+        (
+            "parse_errors",
+            Json::object([
+                ("count", count(parse_errors.len())),
+                ("seeds", seeds(&parse_errors)),
+                (
+                    "replays",
+                    paths(
+                        parse_errors
+                            .iter()
+                            .filter_map(|s| saved(replays, &format!("{s}.res"))),
+                    ),
+                ),
+            ]),
+        ),
+        (
+            "unstarted",
+            Json::object([
+                ("count", count(unstarted.len())),
+                ("seeds", seeds(&unstarted)),
+            ]),
+        ),
+        (
+            "timeouts",
+            by_player(config, results, PlayerStatus::TimedOut),
+        ),
+        (
+            "disqualifications",
+            by_player(config, results, PlayerStatus::Disqualified),
+        ),
+    ])
+}
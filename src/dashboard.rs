@@ -0,0 +1,87 @@
+use crate::results::LiveStats;
+use crate::runner::{ExecutionResults, GameObserver};
+
+use color_eyre::eyre::Result;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::sync::{Mutex, PoisonError};
+
+/// Live view of the run: the progress bar plus running statistics that update as games finish
+pub struct Dashboard {
+    progress: ProgressBar,
+    players: [ProgressBar; 4],
+    footer: ProgressBar,
+    names: [String; 4],
+    stats: Mutex<LiveStats>,
+}
+
+impl Dashboard {
+    pub fn new(
+        total_games: u32,
+        names: &[String; 4],
+    ) -> Result<Self, indicatif::style::TemplateError> {
+        let multi = MultiProgress::new();
+        let line = ProgressStyle::with_template(" {msg}")?;
+
+        let progress = multi.add(ProgressBar::new(total_games.into()).with_style(
+            ProgressStyle::with_template(
+                " Running games... ({pos}/{len}) {wide_bar} {percent}% [{per_sec}, ETA {eta}] ",
+            )?,
+        ));
+        let players = names
+            .clone()
+            .map(|_| multi.add(ProgressBar::new_spinner().with_style(line.clone())));
+        let footer = multi.add(ProgressBar::new_spinner().with_style(line));
+
+        let ret = Self {
+            progress,
+            players,
+            footer,
+            names: names.clone(),
+            stats: Mutex::new(LiveStats::default()),
+        };
+        ret.redraw(&LiveStats::default());
+        Ok(ret)
+    }
+
+    /// Bar tracking the number of finished games
+    pub fn progress_bar(&self) -> ProgressBar {
+        self.progress.clone()
+    }
+
+    fn redraw(&self, stats: &LiveStats) {
+        let games = f64::from(stats.games.max(1));
+        for i in 0..4 {
+            #[allow(clippy::cast_precision_loss)] // Correctness: Only used for display
+            let average = stats.points[i] as f64 / games;
+            self.players[i].set_message(format!(
+                "{:<12} {average:>9.1} points in average {:>6.1}% WR",
+                self.names[i],
+                f64::from(stats.wins[i]) * 100. / games,
+            ));
+        }
+        self.footer.set_message(format!(
+            "{} games finished, {} crashed",
+            stats.games + stats.crashes,
+            stats.crashes
+        ));
+    }
+
+    /// Leaves the progress bar in place and clears the statistics, as the summary comes next
+    pub fn finish(&self) {
+        self.progress.finish();
+        for bar in &self.players {
+            bar.finish_and_clear();
+        }
+        self.footer.finish_and_clear();
+    }
+}
+
+impl GameObserver for Dashboard {
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()> {
+        let mut stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        stats.record(result);
+        self.redraw(&stats);
+        Ok(())
+    }
+}
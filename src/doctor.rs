@@ -0,0 +1,127 @@
+//! Pre-flight checks of everything a run needs, so a broken setup shows up before a long run
+//! instead of as a screen full of crashes.
+
+use crate::build;
+use crate::errors::AppError;
+use crate::runner::{ExecutionResults, GameRunner};
+use crate::{PlayerName, TestConfig};
+
+use color_eyre::eyre::Result;
+use itertools::Itertools;
+use std::{fs, path::Path};
+
+/// File descriptors a game keeps open on our side while it runs: its pipes, the file its output
+/// is saved to and the ends we briefly hold while spawning it
+const FDS_PER_GAME: u64 = 6;
+/// File descriptors used by everything else, like the log file and the standard streams
+const FDS_RESERVED: u64 = 32;
+
+fn check_game(game: &Path) -> Result<String> {
+    let metadata = fs::metadata(game).map_err(|_| AppError::MissingFile(game.to_owned()))?;
+    if !metadata.is_file() {
+        return Err(AppError::MissingFile(game.to_owned()).into());
+    }
+    #[cfg(unix)]
+    if std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o111 == 0 {
+        return Err(AppError::NotExecutable(game.to_owned()).into());
+    }
+    Ok(format!("{} is executable", game.display()))
+}
+
+fn check_settings(path: &str) -> Result<String> {
+    let settings = fs::read_to_string(path).map_err(|_| AppError::MissingFile(path.into()))?;
+    let lines = settings.lines().filter(|l| !l.trim().is_empty()).count();
+    if lines == 0 {
+        return Err(AppError::EmptySettings(path.into()).into());
+    }
+    Ok(format!("{path} has {lines} settings"))
+}
+
+fn check_players(config: &TestConfig) -> Result<String> {
+    let names: Vec<_> = config
+        .players
+        .iter()
+        .map(PlayerName::as_string)
+        .unique()
+        .collect();
+    build::check_registered(&config.game, &names)?;
+    Ok(format!("{} are registered", names.join(", ")))
+}
+
+fn check_file_descriptors(jobs: usize) -> Result<String> {
+    let needed = jobs as u64 * FDS_PER_GAME + FDS_RESERVED;
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit only writes to the struct we hand it
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &raw mut limit) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        if limit.rlim_cur < needed {
+            return Err(AppError::TooFewFileDescriptors(limit.rlim_cur, needed, jobs).into());
+        }
+        Ok(format!(
+            "{} allowed, about {needed} needed with --jobs {jobs}",
+            limit.rlim_cur
+        ))
+    }
+    #[cfg(not(unix))]
+    Ok(format!("not checked, about {needed} needed"))
+}
+
+fn check_probe(config: &TestConfig) -> Result<String> {
+    let runner = GameRunner::new(config, None, None)?;
+    match runner.run_saving(config.seed, None)? {
+        ExecutionResults::Ok { points, usage, .. } => Ok(format!(
+            "seed {} finished in {:.2?} with scores {}",
+            config.seed,
+            usage.duration,
+            points.map(|p| p.to_string()).join(", ")
+        )),
+        ExecutionResults::ParseError { found, .. } => Err(AppError::ScoresNotFound(found).into()),
+        ExecutionResults::Crash { .. } => Err(AppError::ProbeCrashed(config.seed).into()),
+        ExecutionResults::Unstarted { .. } => Err(AppError::ProbeUnstarted(config.seed).into()),
+    }
+}
+
+/// Runs every check, printing how each went. Fails if any of them did
+pub fn run(config: &TestConfig, jobs: usize) -> Result<()> {
+    let checks: [(&str, &dyn Fn() -> Result<String>); 4] = [
+        ("Game", &|| check_game(&config.game)),
+        ("Settings", &|| check_settings(&config.settings_file)),
+        ("Players", &|| check_players(config)),
+        ("File descriptors", &|| check_file_descriptors(jobs)),
+    ];
+
+    let mut failed = 0;
+    for (name, check) in checks {
+        match check() {
+            Ok(detail) => println!("[ok]   {name}: {detail}"),
+            Err(err) => {
+                println!("[FAIL] {name}: {err}");
+                failed += 1;
+            }
+        }
+    }
+    // The probe fails in more confusing ways if something above is wrong
+    if failed == 0 {
+        match check_probe(config) {
+            Ok(detail) => println!("[ok]   Probe game: {detail}"),
+            Err(err) => {
+                println!("[FAIL] Probe game: {err}");
+                failed += 1;
+            }
+        }
+    } else {
+        println!("[skip] Probe game: fix the problems above first");
+    }
+
+    if failed > 0 {
+        return Err(AppError::ChecksFailed(failed).into());
+    }
+    println!("Everything looks ready for a run");
+    Ok(())
+}
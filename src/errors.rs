@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,6 +6,173 @@ pub enum AppError {
     #[error("Seed range goes out of bounds")]
     SeedRangeOutOfBounds,
 
+    #[error("Too many games to run")]
+    TooManyGames,
+
     #[error("Can't communicate with child")]
     BrokenChildCommunication,
-}
\ No newline at end of file
+
+    #[error("Invalid regex: {0}")]
+    InvalidRegex(#[from] regex::Error),
+
+    #[error("The score regex needs a `score` named group or at least one capture group")]
+    MissingScoreGroup,
+
+    #[error("The regex `{0}` needs a `name` named group")]
+    MissingNameGroup(String),
+
+    #[error("The round score regex needs `name` and `score` named groups")]
+    MissingTimelineGroups,
+
+    #[error("The metric regex `{0}` needs at least one named group besides `name`")]
+    MissingMetricGroup(String),
+
+    #[error("Can't find {0}")]
+    MissingFile(PathBuf),
+
+    #[error("Can't launch the viewer on {0}")]
+    ViewerLaunch(String),
+
+    #[error("{0} isn't one of the players")]
+    UnknownPlayer(String),
+
+    #[error("Can't sort the players by `{0}`, expected `wr`, `score`, `seat`, `top`, `norm` (with --normalize) or a metric name")]
+    UnknownSortKey(String),
+
+    #[error("Can't set up worker {0}: {1}")]
+    WorkerSetup(String, String),
+
+    #[error("Unexpected message from the other end of the job queue: {0}")]
+    JobProtocol(String),
+
+    #[error("Can't send {0:?} to the workers, as it spans several lines")]
+    MultilineJobValue(String),
+
+    #[error("Invalid optimization checkpoint {0}")]
+    InvalidCheckpoint(PathBuf),
+
+    #[error("{0} has no `#define PLAYER_NAME` line")]
+    MissingPlayerName(PathBuf),
+
+    #[error("Building the game failed:\n{0}")]
+    BuildFailed(String),
+
+    #[error("{0} isn't executable")]
+    NotExecutable(PathBuf),
+
+    #[error("The settings file {0} is empty")]
+    EmptySettings(PathBuf),
+
+    #[error("Only {0} file descriptors are allowed, but about {1} are needed with --jobs {2}. Raise the limit with `ulimit -n` or lower --jobs")]
+    TooFewFileDescriptors(u64, u64, usize),
+
+    #[error("The score regex only found the score of {0} of the 4 players")]
+    ScoresNotFound(usize),
+
+    #[error("The game crashed on seed {0}")]
+    ProbeCrashed(u32),
+
+    #[error("The game couldn't be started on seed {0}")]
+    ProbeUnstarted(u32),
+
+    #[error("{0} of the checks failed")]
+    ChecksFailed(usize),
+
+    #[error("The run failed: {0}")]
+    RunFailed(String),
+
+    #[error("The games on --workers can only get their settings through stdin")]
+    SettingsViaWorkers,
+
+    #[error("Stopped at the first failure: seed {0} {1}. Look into it with `inspect --seed {0}`")]
+    FailFast(u32, String),
+
+    #[error("--fail-fast can't stop the games of remote workers")]
+    FailFastServeJobs,
+
+    #[error("--load-target needs a positive number of CPUs, got {0}")]
+    InvalidLoadTarget(f64),
+
+    #[error("--load-target only knows the load of this machine, not the one of remote workers")]
+    LoadTargetRemote,
+
+    #[error("{0} needs a positive number, got {1}")]
+    InvalidCpuBudget(&'static str, f64),
+
+    #[error("--enforce-cpu-budget can only limit the games played on this machine")]
+    CpuBudgetRemote,
+
+    #[error("--sandbox can only sandbox the games played on this machine, not on remote workers")]
+    SandboxRemote,
+
+    #[error(
+        "--isolate can only give a directory of their own to the games played on this machine"
+    )]
+    IsolateRemote,
+
+    #[error("Compressing the artifacts failed: {0}")]
+    CompressionFailed(String),
+
+    #[error("--watch rebuilds a single game, but several --game were given")]
+    WatchSeveralGames,
+
+    #[error("--watch compares every build with the previous one, so it doesn't take a --champion")]
+    WatchChampion,
+
+    #[error("--fail-on regression needs a --baseline run to compare with")]
+    MissingBaseline,
+
+    #[error("--fail-on thresholds needs --min-win-rate or --min-score")]
+    MissingThresholds,
+
+    #[error("Player {0} isn't listed by the game's --list")]
+    PlayerNotRegistered(String),
+
+    #[error("Posting to the webhook failed: {0}")]
+    Webhook(String),
+
+    #[error("Invalid configuration in {0}, {1}")]
+    InvalidConfig(PathBuf, String),
+
+    #[error("Unknown option `{1}` in {0}")]
+    UnknownConfigKey(PathBuf, String),
+
+    #[error("There's no preset named {0} in the configuration file")]
+    UnknownPreset(String),
+
+    #[error("--preset can only be used to run the tests, not with other subcommands")]
+    PresetOutsideRun,
+
+    #[error("{0} has no run metadata to reproduce the run from")]
+    MissingMetadata(PathBuf),
+
+    #[error("Can't read the run results in {0}")]
+    InvalidRunFile(PathBuf),
+
+    #[error("Can't read the standings in {0}")]
+    InvalidLeagueFile(PathBuf),
+
+    #[error("The runs have no seeds in common")]
+    NoCommonSeeds,
+
+    #[error("A tournament needs at least 4 players, got {0}")]
+    NotEnoughPlayers(usize),
+
+    #[error("--placement-points needs the points of the 4 places, got {0}")]
+    InvalidPlacementPoints(usize),
+
+    #[error("A gauntlet needs at least 3 opponents in the pool, got {0}")]
+    NotEnoughOpponents(usize),
+
+    #[error("Queued arguments can't span several lines: {0:?}")]
+    InvalidQueueArgument(String),
+
+    #[error("`{0}` can't be queued from the web UI, queue the run with `queue add` instead")]
+    UnqueueableArgument(String),
+
+    #[error("The web UI server stopped unexpectedly")]
+    WebUiStopped,
+
+    #[error("The metrics are already served on {0}, every run has to use the same --metrics-addr")]
+    MetricsAddrChanged(String),
+}
@@ -0,0 +1,31 @@
+use crate::results::TestResults;
+use std::collections::BTreeMap;
+
+/// Outcome of a game: `None` stands for a crash, and `Some` holds the seats that won
+pub type Outcome = Option<[bool; 4]>;
+
+/// Seed whose outcome wasn't the same across repetitions
+pub struct FlakySeed {
+    pub seed: u32,
+    /// How many times each outcome happened
+    pub outcomes: Vec<(Outcome, u32)>,
+}
+
+pub fn find(results: &TestResults) -> Vec<FlakySeed> {
+    let mut seeds: BTreeMap<u32, Vec<(Outcome, u32)>> = BTreeMap::new();
+
+    for game in &results.games {
+        let outcomes = seeds.entry(game.seed).or_default();
+        let outcome = game.winners();
+        match outcomes.iter_mut().find(|(o, _)| *o == outcome) {
+            Some((_, count)) => *count += 1,
+            None => outcomes.push((outcome, 1)),
+        }
+    }
+
+    seeds
+        .into_iter()
+        .filter(|(_, outcomes)| outcomes.len() > 1)
+        .map(|(seed, outcomes)| FlakySeed { seed, outcomes })
+        .collect()
+}
@@ -0,0 +1,52 @@
+use crate::results::TestResults;
+
+/// A game where the focal player did notably badly
+pub struct WorstSeed {
+    pub seed: u32,
+    pub points: u32,
+    /// Points below the player's own average
+    pub deficit: f64,
+    pub lost: bool,
+}
+
+/// Lists the `count` games where the player in `seat` did worst, as a debugging worklist.
+///
+/// If the player wins most of its games, its losses come first. Otherwise (or within the
+/// losses), games are sorted by how far below its average it scored.
+pub fn worst_seeds(results: &TestResults, seat: usize, count: usize) -> Vec<WorstSeed> {
+    let games: Vec<_> = results
+        .games
+        .iter()
+        .filter_map(|g| Some((g.seed, g.points?)))
+        .collect();
+    if games.is_empty() {
+        return Vec::new();
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Correctness: We can't run more than u32::MAX seeds
+    let total = games.len() as f64;
+    let average = games.iter().map(|(_, p)| f64::from(p[seat])).sum::<f64>() / total;
+
+    let mut ret: Vec<_> = games
+        .into_iter()
+        .map(|(seed, points)| WorstSeed {
+            seed,
+            points: points[seat],
+            deficit: average - f64::from(points[seat]),
+            lost: points.iter().any(|&p| p > points[seat]),
+        })
+        .collect();
+
+    #[allow(clippy::cast_precision_loss)] // Correctness: We can't run more than u32::MAX seeds
+    let usually_wins = ret.iter().filter(|s| !s.lost).count() as f64 * 2. >= total;
+    ret.sort_by(|a, b| {
+        let losses_first = if usually_wins {
+            b.lost.cmp(&a.lost)
+        } else {
+            std::cmp::Ordering::Equal
+        };
+        losses_first.then(b.deficit.total_cmp(&a.deficit))
+    });
+    ret.truncate(count);
+    ret
+}
@@ -0,0 +1,97 @@
+//! Gauntlets of a player against mixes of opponents drawn from a pool.
+
+use crate::errors::AppError;
+use crate::opponents::{Breakdown, Record};
+use crate::seed_plan::{Layout, SeedPlan};
+use crate::{parse_players, TestConfig};
+
+use color_eyre::eyre::Result;
+use itertools::Itertools;
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+use std::{io, num::NonZeroU32, path::PathBuf};
+
+/// Player under test, the pool of opponents and the seed range split between the mixes
+pub struct Gauntlet {
+    pub player: String,
+    pub pool: Vec<String>,
+    pub mixes: NonZeroU32,
+    pub seed: u32,
+    pub instances: NonZeroU32,
+    pub settings_file: String,
+    pub game: PathBuf,
+    /// Only print the mixes and their seeds
+    pub dry_run: bool,
+    /// File the seeds of every mix are written to
+    pub seed_plan: Option<PathBuf>,
+}
+
+impl Gauntlet {
+    /// Draws the mixes of three opponents, splits the seed range between them and reports the
+    /// results of the player against each of them
+    pub fn run(&self) -> Result<()> {
+        if self.pool.len() < 3 {
+            return Err(AppError::NotEnoughOpponents(self.pool.len()).into());
+        }
+
+        // Seeded by the first seed, so the same range always draws the same mixes and seats
+        let mut rng = SmallRng::seed_from_u64(self.seed.into());
+        let combinations: Vec<Vec<&String>> = self.pool.iter().combinations(3).collect();
+        let count = [self.mixes.get(), self.instances.get()]
+            .into_iter()
+            .chain(u32::try_from(combinations.len()).ok())
+            .min()
+            .unwrap_or(1);
+        let mixes: Vec<_> = combinations
+            .choose_multiple(&mut rng, count as usize)
+            .collect();
+
+        let mut seed = self.seed;
+        let mut seed_plan = SeedPlan::new("mixes", Layout::Disjoint);
+        let mut breakdown = Breakdown::default();
+        for (i, opponents) in (0..count).zip(&mixes) {
+            // The first mixes take the seeds left over from an uneven split
+            let instances =
+                self.instances.get() / count + u32::from(i < self.instances.get() % count);
+            let mut names: Vec<String> = opponents.iter().map(|&o| o.clone()).collect();
+            names.push(self.player.clone());
+            names.shuffle(&mut rng);
+            println!(
+                "Mix {}/{count}: {}, seeds {seed} to {}",
+                i + 1,
+                names.join(", "),
+                seed.wrapping_add(instances - 1)
+            );
+            seed_plan.record(format!("mix {}", i + 1), seed, instances);
+            if self.dry_run {
+                seed = seed.wrapping_add(instances);
+                continue;
+            }
+            let seat = names
+                .iter()
+                .position(|n| *n == self.player)
+                .unwrap_or_default();
+
+            let mut config = TestConfig::new(
+                parse_players(names.try_into().expect("a mix has four players")),
+                self.settings_file.clone(),
+                self.game.clone(),
+            );
+            config.seed = seed;
+            config.instances = NonZeroU32::new(instances).expect("there's a seed for every mix");
+            config.quiet = true;
+            let test_results = crate::run_tests(&config)?;
+            seed = seed.wrapping_add(instances);
+
+            let opponents: Vec<String> = opponents.iter().map(|&o| o.clone()).collect();
+            breakdown.add(&opponents, &Record::of(&test_results, seat));
+        }
+
+        seed_plan.report(&mut io::stdout(), self.seed_plan.as_deref())?;
+        if self.dry_run {
+            return Ok(());
+        }
+        println!();
+        breakdown.print(&self.player);
+        Ok(())
+    }
+}
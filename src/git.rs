@@ -0,0 +1,47 @@
+use crate::json::Json;
+
+use std::process::Command;
+
+/// Commit the game folder is at, when it's a git repository
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub commit: String,
+    /// Whether there were uncommitted changes
+    pub dirty: bool,
+}
+
+impl Revision {
+    /// Revision of the current directory, if it's inside a git repository
+    pub fn current() -> Option<Self> {
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
+        };
+
+        Some(Self {
+            commit: git(&["rev-parse", "HEAD"])?,
+            dirty: !git(&["status", "--porcelain"])?.is_empty(),
+        })
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("commit", self.commit.as_str().into()),
+            ("dirty", self.dirty.into()),
+        ])
+    }
+}
+
+impl std::fmt::Display for Revision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.commit)?;
+        if self.dirty {
+            f.write_str(" (dirty)")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+//! Output for GitHub Actions, which shows annotations and job summaries on the pull request.
+
+use crate::report;
+use crate::results::TestResults;
+use crate::TestConfig;
+
+use color_eyre::eyre::Result;
+use itertools::Itertools;
+use std::{env, fs::OpenOptions, io::Write};
+
+/// Whether we're running in a GitHub Actions job
+pub fn is_enabled() -> bool {
+    env::var_os("GITHUB_ACTIONS").is_some_and(|v| v == "true")
+}
+
+/// Prints a workflow annotation for every seed that crashed, couldn't be parsed or couldn't be
+/// started
+pub fn annotate(results: &TestResults) {
+    for seed in results.failed_seeds.iter().sorted().dedup() {
+        println!("::error title=Game crashed::Seed {seed} crashed");
+    }
+    for seed in results.parse_error_seeds.iter().sorted().dedup() {
+        println!(
+            "::error title=Scores not found::The score regex didn't find every score of seed {seed}"
+        );
+    }
+    for seed in results.unstarted_seeds.iter().sorted().dedup() {
+        println!(
+            "::warning title=Game not started::Seed {seed} couldn't be started, even after retrying"
+        );
+    }
+}
+
+/// Adds the results of the run to the job summary, if the job has one
+pub fn write_summary(
+    config: &TestConfig,
+    results: &TestResults,
+    metric_names: &[String],
+) -> Result<()> {
+    let Some(path) = env::var_os("GITHUB_STEP_SUMMARY") else {
+        return Ok(());
+    };
+
+    // Other steps may have written to the summary already
+    let mut out = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        out,
+        "### {}\n",
+        config.players.map(|p| p.as_string()).join(", ")
+    )?;
+    writeln!(
+        out,
+        "{} games from seed {}\n",
+        results.games.len(),
+        config.seed
+    )?;
+    report::write_markdown(&mut out, config, results, metric_names)?;
+
+    let crashed: Vec<_> = results
+        .failed_seeds
+        .iter()
+        .sorted()
+        .dedup()
+        .map(u32::to_string)
+        .collect();
+    if !crashed.is_empty() {
+        writeln!(out, "\n**Crashed seeds:** {}", crashed.join(", "))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
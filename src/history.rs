@@ -0,0 +1,240 @@
+//! Browsing and comparing the runs saved with `--artifacts`.
+
+use crate::errors::AppError;
+use crate::regressions::{self, Scores};
+use crate::results::TestResults;
+
+use color_eyre::eyre::Result;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Results of a saved run, read back from its `seeds.csv`
+struct SavedRun {
+    players: [String; 4],
+    /// Scores of every seed, or `None` for the crashes
+    games: HashMap<u32, Option<[u32; 4]>>,
+}
+
+impl SavedRun {
+    fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("seeds.csv");
+        let contents =
+            fs::read_to_string(&path).map_err(|_| AppError::MissingFile(path.clone()))?;
+        let invalid = || AppError::InvalidRunFile(path.clone());
+
+        let mut lines = contents.lines();
+        let header: Vec<_> = lines.next().ok_or_else(invalid)?.split(',').collect();
+        let players: [String; 4] = match header[..] {
+            ["seed", "status", ref players @ ..] => players
+                .iter()
+                .map(|&p| p.to_owned())
+                .collect::<Vec<_>>()
+                .try_into()
+                .map_err(|_| invalid())?,
+            _ => return Err(invalid().into()),
+        };
+
+        let mut games = HashMap::new();
+        for line in lines {
+            let fields: Vec<_> = line.split(',').collect();
+            let seed = fields[0].parse().map_err(|_| invalid())?;
+            let points = match fields.get(1) {
+                Some(&"ok") => Some(
+                    fields[2..]
+                        .iter()
+                        .map(|p| p.parse().ok())
+                        .collect::<Option<Vec<u32>>>()
+                        .and_then(|p| p.try_into().ok())
+                        .ok_or_else(invalid)?,
+                ),
+                Some(&"crash") => None,
+                _ => return Err(invalid().into()),
+            };
+            games.insert(seed, points);
+        }
+
+        Ok(Self { players, games })
+    }
+
+    fn scores(&self) -> Scores {
+        self.games
+            .iter()
+            .filter_map(|(&seed, points)| Some((seed, (*points)?)))
+            .collect()
+    }
+
+    fn crashes(&self) -> usize {
+        self.games.values().filter(|g| g.is_none()).count()
+    }
+
+    /// Average score and win rate of every seat, over the given seeds
+    fn stats<'a>(&self, seeds: impl IntoIterator<Item = &'a u32>) -> [(f64, f64); 4] {
+        let mut points = [0.; 4];
+        let mut wins = [0.; 4];
+        let mut games = 0.;
+        for seed in seeds {
+            let Some(Some(scores)) = self.games.get(seed) else {
+                continue;
+            };
+            let max = scores.iter().max().copied().unwrap_or_default();
+            for i in 0..4 {
+                points[i] += f64::from(scores[i]);
+                if scores[i] == max {
+                    wins[i] += 1.;
+                }
+            }
+            games += 1.;
+        }
+        let games = f64::max(games, 1.);
+        [0, 1, 2, 3].map(|i| (points[i] / games, wins[i] * 100. / games))
+    }
+
+    /// Seats with the highest score on `seed`, if it didn't crash
+    fn winners(&self, seed: u32) -> Option<[bool; 4]> {
+        let scores = (*self.games.get(&seed)?)?;
+        let max = scores.iter().max().copied().unwrap_or_default();
+        Some(scores.map(|p| p == max))
+    }
+}
+
+/// Win rates of `seat` in the saved run and in `results`, over the seeds both played
+pub fn win_rates(baseline: &Path, results: &TestResults, seat: usize) -> Result<(f64, f64)> {
+    let old = SavedRun::load(baseline)?;
+    let mut seeds = Vec::new();
+    let mut wins = 0.;
+    for game in &results.games {
+        let Some(winners) = game.winners() else {
+            continue;
+        };
+        if old.games.get(&game.seed).is_some_and(Option::is_some) {
+            seeds.push(game.seed);
+            if winners[seat] {
+                wins += 1.;
+            }
+        }
+    }
+    if seeds.is_empty() {
+        return Err(AppError::NoCommonSeeds.into());
+    }
+
+    let new = wins * 100. / f64::from(u32::try_from(seeds.len()).unwrap_or(u32::MAX));
+    Ok((old.stats(&seeds)[seat].1, new))
+}
+
+/// Value of a `key = value` line of a run's `config.txt`
+pub fn config_value(dir: &Path, key: &str) -> Option<String> {
+    let contents = fs::read_to_string(dir.join("config.txt")).ok()?;
+    contents.lines().find_map(|line| {
+        let (k, value) = line.split_once(" = ")?;
+        (k == key).then(|| value.trim_matches('"').to_owned())
+    })
+}
+
+/// Lists the runs saved in `base`, oldest first
+pub fn list(base: &Path) -> Result<()> {
+    let mut runs: Vec<PathBuf> = fs::read_dir(base)
+        .map_err(|_| AppError::MissingFile(base.to_owned()))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.join("manifest.json").exists())
+        .collect();
+    runs.sort();
+
+    if runs.is_empty() {
+        println!("No saved runs in {}", base.display());
+        return Ok(());
+    }
+    for dir in runs {
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let Ok(run) = SavedRun::load(&dir) else {
+            println!("{name}: no results");
+            continue;
+        };
+        let mut parts = vec![run.players.join(", "), format!("{} games", run.games.len())];
+        if run.crashes() > 0 {
+            parts.push(format!("{} crashed", run.crashes()));
+        }
+        if let Some(revision) = config_value(&dir, "git_revision").filter(|r| !r.is_empty()) {
+            parts.push(format!("game at {revision}"));
+        }
+        println!("{name}: {}", parts.join(", "));
+    }
+    Ok(())
+}
+
+/// Scores of every seed of a saved run that didn't crash
+pub fn saved_scores(dir: &Path) -> Result<Scores> {
+    Ok(SavedRun::load(dir)?.scores())
+}
+
+/// Compares the results of two saved runs, over the seeds both played, listing the first
+/// `drops` seeds where `player` (the first one by default) placed worse. Returns those seeds
+pub fn compare(
+    baseline: &Path,
+    candidate: &Path,
+    player: Option<&str>,
+    drops: usize,
+) -> Result<Vec<u32>> {
+    let (old, new) = (SavedRun::load(baseline)?, SavedRun::load(candidate)?);
+    let seat = match player {
+        Some(name) => new
+            .players
+            .iter()
+            .position(|p| p == name)
+            .ok_or_else(|| AppError::UnknownPlayer(name.to_owned()))?,
+        None => 0,
+    };
+    let mut seeds: Vec<_> = old
+        .games
+        .keys()
+        .filter(|s| new.games.contains_key(s))
+        .copied()
+        .collect();
+    seeds.sort_unstable();
+    if seeds.is_empty() {
+        return Err(AppError::NoCommonSeeds.into());
+    }
+
+    println!(
+        "Comparing {} with {} over {} common seeds:",
+        candidate.display(),
+        baseline.display(),
+        seeds.len()
+    );
+    let (old_stats, new_stats) = (old.stats(&seeds), new.stats(&seeds));
+    for i in 0..4 {
+        let ((old_score, old_wr), (new_score, new_wr)) = (old_stats[i], new_stats[i]);
+        let name = if old.players[i] == new.players[i] {
+            old.players[i].clone()
+        } else {
+            format!("{} (was {})", new.players[i], old.players[i])
+        };
+        println!(
+            "=> Player {name}: {new_score:.1} points in average ({:+.1}), {new_wr:.1}% WR ({:+.1})",
+            new_score - old_score,
+            new_wr - old_wr
+        );
+    }
+
+    let crashes = |run: &SavedRun| seeds.iter().filter(|s| run.games[s].is_none()).count();
+    println!("Crashes: {} before, {} now", crashes(&old), crashes(&new));
+    let changed: Vec<_> = seeds
+        .iter()
+        .filter(|&&s| old.winners(s) != new.winners(s))
+        .map(u32::to_string)
+        .collect();
+    if !changed.is_empty() {
+        println!(
+            "The winners changed in {} seeds: {}",
+            changed.len(),
+            changed.join(", ")
+        );
+    }
+
+    let found = regressions::find(&old.scores(), &new.scores(), seat);
+    regressions::write(&mut io::stdout(), &found, &new.players[seat], drops)?;
+    Ok(found.iter().take(drops).map(|drop| drop.seed).collect())
+}
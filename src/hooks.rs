@@ -0,0 +1,123 @@
+//! User commands run before and after every game, to plug the run into other tools.
+//!
+//! The commands run through the shell with the seed as their first argument, and get the game in
+//! `EDA_*` environment variables: `EDA_SEED` and `EDA_PLAYERS` (comma-separated) for both, plus
+//! `EDA_STATUS` (`ok`, `crash`, `parse-error` or `unstarted`), `EDA_CRASH_KIND` (like `segfault`,
+//! `abort`, `timeout`, `killed` or `exit-1`), `EDA_SCORES`, `EDA_WINNERS`, `EDA_DURATION_MS`
+//! and, when they were saved, `EDA_OUTPUT` and `EDA_CRASH_DUMP` for the post-game hook.
+
+use crate::runner::{ExecutionResults, GameObserver};
+
+use color_eyre::eyre::Result;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::{debug, warn};
+
+pub struct Hooks {
+    pre_game: Option<String>,
+    post_game: Option<String>,
+    players: [String; 4],
+    output_dir: Option<PathBuf>,
+    crash_dumps: Option<PathBuf>,
+}
+
+impl Hooks {
+    pub fn new(
+        pre_game: Option<String>,
+        post_game: Option<String>,
+        players: &[String; 4],
+        output_dir: Option<&Path>,
+        crash_dumps: Option<&Path>,
+    ) -> Self {
+        Self {
+            pre_game,
+            post_game,
+            players: players.clone(),
+            output_dir: output_dir.map(Path::to_owned),
+            crash_dumps: crash_dumps.map(Path::to_owned),
+        }
+    }
+
+    /// Runs a hook, only warning if it fails, as the run doesn't depend on it
+    fn run(&self, hook: &str, seed: u32, env: &[(&str, String)]) {
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(hook).arg(seed.to_string());
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(hook).arg("sh").arg(seed.to_string());
+            command
+        };
+        command
+            .env("EDA_SEED", seed.to_string())
+            .env("EDA_PLAYERS", self.players.join(","))
+            .envs(env.iter().map(|(k, v)| (k, v)));
+        debug!(seed, hook, "Running hook");
+        match command.status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => warn!(seed, hook, %status, "Hook failed"),
+            Err(err) => warn!(seed, hook, %err, "Couldn't run the hook"),
+        }
+    }
+
+    /// Saved files of the seed, if they exist
+    fn file(dir: Option<&Path>, name: &str) -> Option<String> {
+        let path = dir?.join(name);
+        path.exists().then(|| path.to_string_lossy().into_owned())
+    }
+}
+
+impl GameObserver for Hooks {
+    fn game_started(&self, seed: u32) -> Result<()> {
+        if let Some(hook) = &self.pre_game {
+            self.run(hook, seed, &[]);
+        }
+        Ok(())
+    }
+
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()> {
+        let Some(hook) = &self.post_game else {
+            return Ok(());
+        };
+        let seed = result.seed();
+        let join = |values: &[String]| values.join(",");
+
+        let mut env = vec![("EDA_DURATION_MS", result.duration().as_millis().to_string())];
+        match result {
+            ExecutionResults::Ok { points, .. } => {
+                let max = points.iter().max().copied().unwrap_or_default();
+                let winners: Vec<_> = (0..4)
+                    .filter(|&i| points[i] == max)
+                    .map(|i| self.players[i].clone())
+                    .collect();
+                env.extend([
+                    ("EDA_STATUS", "ok".to_owned()),
+                    ("EDA_SCORES", join(&points.map(|p| p.to_string()))),
+                    ("EDA_WINNERS", join(&winners)),
+                ]);
+            }
+            ExecutionResults::Crash { kind, .. } => env.extend([
+                ("EDA_STATUS", "crash".to_owned()),
+                ("EDA_CRASH_KIND", kind.id()),
+            ]),
+            ExecutionResults::ParseError { .. } => {
+                env.push(("EDA_STATUS", "parse-error".to_owned()));
+            }
+            ExecutionResults::Unstarted { .. } => {
+                env.push(("EDA_STATUS", "unstarted".to_owned()));
+            }
+        }
+        if let Some(path) = Self::file(self.output_dir.as_deref(), &format!("{seed}.res")) {
+            env.push(("EDA_OUTPUT", path));
+        }
+        if let Some(path) = Self::file(self.crash_dumps.as_deref(), &format!("{seed}.stderr")) {
+            env.push(("EDA_CRASH_DUMP", path));
+        }
+
+        self.run(hook, seed, &env);
+        Ok(())
+    }
+}
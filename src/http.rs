@@ -0,0 +1,130 @@
+//! Bare-bones HTTP/1.0 server for the pages the tester exposes
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+/// Largest request body read, more than any form of the pages needs
+const MAX_BODY: usize = 64 * 1024;
+
+/// How long a client can take to send its request or read the response before it's dropped
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    /// Body of a `POST`, empty otherwise
+    pub body: String,
+}
+
+pub struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of the header `name`, if it was sent
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn forbidden() -> Self {
+        Self {
+            status: "403 Forbidden",
+            content_type: "text/plain",
+            body: b"Forbidden\n".to_vec(),
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Self {
+            status: "404 Not Found",
+            content_type: "text/plain",
+            body: b"Not found\n".to_vec(),
+        }
+    }
+}
+
+fn respond(stream: TcpStream, handler: &impl Fn(&Request) -> Response) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut words = line.split_whitespace();
+    let method = words.next().unwrap_or("GET").to_owned();
+    let path = words.next().unwrap_or("/").to_owned();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+    }
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length.min(MAX_BODY)];
+    reader.read_exact(&mut body)?;
+
+    let response = handler(&Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    });
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)
+}
+
+/// Answers every request with `handler` on a background thread, each connection on its own
+/// thread so a slow client doesn't hold up the others
+pub fn serve(
+    listener: TcpListener,
+    handler: impl Fn(&Request) -> Response + Send + Sync + 'static,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        thread::scope(|scope| {
+            for stream in listener.incoming().flatten() {
+                let handler = &handler;
+                scope.spawn(move || {
+                    if let Err(err) = respond(stream, handler) {
+                        tracing::debug!(%err, "HTTP request failed");
+                    }
+                });
+            }
+        });
+    })
+}
@@ -0,0 +1,52 @@
+//! Playing a seed of a run again on its own, with the game's output live in the terminal and its
+//! replay saved, to look into a crash or an odd score the statistics pointed at.
+
+use crate::errors::AppError;
+use crate::parser::PlayerStatus;
+use crate::runner::{ExecutionResults, GameRunner};
+use crate::viewer;
+use crate::TestConfig;
+
+use color_eyre::eyre::Result;
+use std::path::PathBuf;
+
+/// Plays `config.seed` once, saving its replay to `replay` (`SEED.res` by default) and opening it
+/// in the viewer if `view` is set
+pub fn run(config: &TestConfig, replay: Option<PathBuf>, view: bool) -> Result<()> {
+    let seed = config.seed;
+    let replay = replay.unwrap_or_else(|| PathBuf::from(format!("{seed}.res")));
+    let runner = GameRunner::new(config, None, None)?.with_live_output();
+
+    eprintln!(
+        "Playing seed {seed} with {}, the game's output follows",
+        runner.player_names().join(", ")
+    );
+    let result = runner.run_saving(seed, Some(&replay))?;
+    println!();
+    match &result {
+        ExecutionResults::Ok { points, status, .. } => {
+            let max = points.iter().max().copied().unwrap_or_default();
+            for ((name, points), status) in runner.player_names().iter().zip(points).zip(status) {
+                let status = match status {
+                    PlayerStatus::Alive => "",
+                    PlayerStatus::TimedOut => ", timed out",
+                    PlayerStatus::Disqualified => ", disqualified",
+                };
+                let winner = if *points == max { " (winner)" } else { "" };
+                println!("{name}: {points} points{status}{winner}");
+            }
+        }
+        ExecutionResults::Crash { signature, .. } => println!("The game crashed: {signature}"),
+        ExecutionResults::ParseError { found, .. } => {
+            println!("The score regex only found the score of {found} of the 4 players");
+        }
+        ExecutionResults::Unstarted { .. } => return Err(AppError::ProbeUnstarted(seed).into()),
+    }
+    println!("Finished in {:.2?}", result.duration());
+    println!("Replay saved in {}", replay.display());
+
+    if view {
+        viewer::open(&config.viewer, &replay)?;
+    }
+    Ok(())
+}
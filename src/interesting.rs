@@ -0,0 +1,78 @@
+use crate::results::TestResults;
+
+/// A game worth studying, with the reason it was picked
+pub struct NotableGame {
+    pub label: &'static str,
+    pub seed: u32,
+}
+
+/// Picks the notable games of a run: the narrowest win, the biggest upset against the average
+/// standings and the worst loss of the focal player
+pub fn find(results: &TestResults, focus: usize) -> Vec<NotableGame> {
+    let games: Vec<_> = results
+        .games
+        .iter()
+        .filter_map(|g| Some((g.seed, g.points?, g)))
+        .collect();
+    if games.is_empty() {
+        return Vec::new();
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Correctness: We can't run more than u32::MAX seeds
+    let averages = results
+        .player_results
+        .each_ref()
+        .map(|r| f64::from(r.total_points) / games.len() as f64);
+    let best_average = averages.iter().copied().fold(f64::MIN, f64::max);
+    let winner = |points: &[u32; 4]| (0..4).max_by_key(|&i| points[i]).unwrap_or_default();
+
+    let mut ret = Vec::new();
+
+    if let Some((seed, ..)) = games.iter().min_by_key(|(seed, _, g)| (g.margin(), *seed)) {
+        ret.push(NotableGame {
+            label: "closest",
+            seed: *seed,
+        });
+    }
+
+    if let Some((seed, ..)) = games
+        .iter()
+        .map(|(seed, points, _)| (seed, best_average - averages[winner(points)]))
+        .filter(|(_, gap)| *gap > 0.)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+    {
+        ret.push(NotableGame {
+            label: "upset",
+            seed: *seed,
+        });
+    }
+
+    if let Some((seed, ..)) = games
+        .iter()
+        .map(|(seed, points, _)| (seed, points[winner(points)] - points[focus]))
+        .filter(|(_, gap)| *gap > 0)
+        .max_by_key(|(_, gap)| *gap)
+    {
+        ret.push(NotableGame {
+            label: "worst-loss",
+            seed: *seed,
+        });
+    }
+
+    ret
+}
+
+/// Seeds of the games that didn't crash, from the most to the least worth keeping: the notable
+/// games first, then the closest ones
+pub fn ranked(results: &TestResults, focus: usize) -> Vec<u32> {
+    let mut ret: Vec<_> = find(results, focus).into_iter().map(|g| g.seed).collect();
+    let mut closest: Vec<_> = results
+        .games
+        .iter()
+        .filter_map(|g| Some((g.margin()?, g.seed)))
+        .filter(|(_, seed)| !ret.contains(seed))
+        .collect();
+    closest.sort_unstable();
+    ret.extend(closest.into_iter().map(|(_, seed)| seed));
+    ret
+}
@@ -0,0 +1,441 @@
+//! Coordinator/worker mode: the coordinator hands out batches of seeds over TCP and the workers
+//! play them and send back the raw game stderr, which is parsed on the coordinator.
+//!
+//! The protocol is line based. On connection the coordinator sends `players <names>`,
+//! `settings <len> <mode>` followed by the settings file and how the game gets it, and
+//! `env <count>` followed by that many `KEY=VALUE` lines, then `capture <limit> <count>` followed
+//! by that many lines with the patterns of the output lines that are always kept. Workers then
+//! ask for work with `next`, and get `batch <seeds>`, `wait` (everything is handed out, but some
+//! batch may still come back) or `done`. Every game is reported with
+//! `result <seed> ok|<crash kind> <duration_us> <user_us>|- <system_us>|- <max_rss>|- <len>`
+//! followed by the kept lines of its stderr, the crash kind being like `segfault` or `exit-1`.
+
+use crate::errors::AppError;
+use crate::results::{OrderedResults, TestResults};
+use crate::runner::{
+    self, Capture, CrashKind, ExecutionResults, GameObserver, GameRunner, Settings, SettingsVia,
+};
+use crate::usage::{CpuTime, ResourceUsage};
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    num::NonZeroU32,
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+    sync::{Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// How often idle workers and the coordinator check for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Where the coordinator listens and how many seeds it hands out at once
+#[derive(Clone)]
+pub struct ServeJobs {
+    pub listen: String,
+    pub batch: NonZeroU32,
+}
+
+enum Work {
+    Batch(Vec<u32>),
+    Wait,
+    Done,
+}
+
+struct QueueState {
+    pending: VecDeque<u32>,
+    remaining: usize,
+    results: OrderedResults,
+}
+
+struct Queue {
+    state: Mutex<QueueState>,
+}
+
+impl Queue {
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn take(&self, count: usize) -> Work {
+        let mut state = self.lock();
+        if state.remaining == 0 {
+            Work::Done
+        } else if state.pending.is_empty() {
+            Work::Wait
+        } else {
+            let count = count.min(state.pending.len());
+            Work::Batch(state.pending.drain(..count).collect())
+        }
+    }
+
+    fn finish(&self, result: ExecutionResults) {
+        let mut state = self.lock();
+        state.remaining -= 1;
+        state.results.push(result.seed(), result.into());
+    }
+
+    /// Puts back the seeds a lost worker didn't report
+    fn requeue(&self, seeds: Vec<u32>) {
+        self.lock().pending.extend(seeds);
+    }
+
+    fn is_done(&self) -> bool {
+        self.lock().remaining == 0
+    }
+}
+
+/// Turns on TCP keepalive, so that a peer whose machine or network died without closing the
+/// connection is noticed in about a minute instead of blocking the reads forever
+#[cfg(unix)]
+fn keep_alive(stream: &TcpStream) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let set = |level, option, value: libc::c_int| {
+        // SAFETY: The value outlives the call and the length is the one of its type
+        let ret = unsafe {
+            libc::setsockopt(
+                stream.as_raw_fd(),
+                level,
+                option,
+                (&raw const value).cast(),
+                libc::socklen_t::try_from(size_of::<libc::c_int>()).unwrap_or_default(),
+            )
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    };
+    set(libc::SOL_SOCKET, libc::SO_KEEPALIVE, 1)?;
+    // Elsewhere the first probe waits for the system default, usually two hours
+    #[cfg(target_os = "linux")]
+    {
+        set(libc::IPPROTO_TCP, libc::TCP_KEEPIDLE, 30)?;
+        set(libc::IPPROTO_TCP, libc::TCP_KEEPINTVL, 10)?;
+        set(libc::IPPROTO_TCP, libc::TCP_KEEPCNT, 3)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn keep_alive(_stream: &TcpStream) -> io::Result<()> {
+    Ok(())
+}
+
+fn protocol_error(line: &str) -> AppError {
+    AppError::JobProtocol(line.trim().to_owned())
+}
+
+/// Reads a line, failing if the connection was closed
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(line)
+}
+
+fn read_payload(reader: &mut impl Read, len: &str) -> Result<String> {
+    let len = len.parse().map_err(|_| protocol_error(len))?;
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    Ok(String::from_utf8_lossy(&payload).into_owned())
+}
+
+/// Runs every game of `seeds`, every seed `repeat` times in a row, on the workers that connect to
+/// the coordinator
+pub fn serve(
+    config: &ServeJobs,
+    runner: &GameRunner,
+    seeds: Vec<u32>,
+    repeat: u32,
+    observers: &[Box<dyn GameObserver>],
+    pb: &ProgressBar,
+) -> Result<TestResults> {
+    // Sent a line each, so a line break would be read as the next message
+    let env = runner
+        .env()
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"));
+    let patterns = runner.capture().patterns().iter().cloned();
+    if let Some(line) = env.chain(patterns).find(|line| line.contains('\n')) {
+        return Err(AppError::MultilineJobValue(line).into());
+    }
+
+    let listener = TcpListener::bind(&config.listen)?;
+    listener.set_nonblocking(true)?;
+    println!("Waiting for workers on {}", listener.local_addr()?);
+
+    let queue = Queue {
+        state: Mutex::new(QueueState {
+            remaining: seeds.len(),
+            results: OrderedResults::new(seeds.first().copied().unwrap_or_default(), repeat),
+            pending: seeds.into(),
+        }),
+    };
+
+    thread::scope(|scope| -> Result<()> {
+        while !queue.is_done() {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    info!(%peer, "Worker connected");
+                    stream.set_nonblocking(false)?;
+                    keep_alive(&stream)?;
+                    let queue = &queue;
+                    scope.spawn(move || {
+                        let mut outstanding = Vec::new();
+                        let res = handle_worker(
+                            stream,
+                            config,
+                            runner,
+                            queue,
+                            observers,
+                            pb,
+                            &mut outstanding,
+                        );
+                        if let Err(err) = res {
+                            warn!(%peer, %err, "Lost worker");
+                            queue.requeue(outstanding);
+                        }
+                    });
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    })?;
+    pb.finish();
+
+    let state = queue
+        .state
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner);
+    Ok(state.results.finish())
+}
+
+fn handle_worker(
+    stream: TcpStream,
+    config: &ServeJobs,
+    runner: &GameRunner,
+    queue: &Queue,
+    observers: &[Box<dyn GameObserver>],
+    pb: &ProgressBar,
+    outstanding: &mut Vec<u32>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    writeln!(writer, "players {}", runner.player_names().join(" "))?;
+    writeln!(
+        writer,
+        "settings {} {}",
+        runner.settings().len(),
+        runner.settings_via().name()
+    )?;
+    writer.write_all(runner.settings().as_bytes())?;
+    writeln!(writer, "env {}", runner.env().len())?;
+    for (key, value) in runner.env() {
+        writeln!(writer, "{key}={value}")?;
+    }
+    let capture = runner.capture();
+    writeln!(
+        writer,
+        "capture {} {}",
+        capture.limit(),
+        capture.patterns().len()
+    )?;
+    for pattern in capture.patterns() {
+        writeln!(writer, "{pattern}")?;
+    }
+
+    loop {
+        let line = read_line(&mut reader)?;
+        let words: Vec<_> = line.split_whitespace().collect();
+        match words[..] {
+            ["next"] => match queue.take(config.batch.get() as usize) {
+                Work::Batch(seeds) => {
+                    for &seed in &seeds {
+                        for observer in observers {
+                            observer.game_started(seed)?;
+                        }
+                    }
+                    writeln!(
+                        writer,
+                        "batch {}",
+                        seeds
+                            .iter()
+                            .map(u32::to_string)
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )?;
+                    outstanding.extend(seeds);
+                }
+                Work::Wait => writeln!(writer, "wait")?,
+                Work::Done => {
+                    writeln!(writer, "done")?;
+                    return Ok(());
+                }
+            },
+            ["result", seed, status, duration, user, system, max_rss, len] => {
+                let seed: u32 = seed.parse().map_err(|_| protocol_error(&line))?;
+                let output = read_payload(&mut reader, len)?;
+                let position = outstanding
+                    .iter()
+                    .position(|&s| s == seed)
+                    .ok_or_else(|| protocol_error(&line))?;
+                outstanding.swap_remove(position);
+
+                let micros = |field: &str| field.parse().ok().map(Duration::from_micros);
+                let usage = ResourceUsage {
+                    duration: micros(duration).ok_or_else(|| protocol_error(&line))?,
+                    cpu: micros(user)
+                        .zip(micros(system))
+                        .map(|(user, system)| CpuTime { user, system }),
+                    max_rss: max_rss.parse().ok(),
+                };
+                if status != "ok" {
+                    warn!(seed, "Game crashed on a worker");
+                }
+
+                let failure = (status != "ok").then(|| CrashKind::from_id(status));
+                let result = runner.parse(seed, failure, &output, usage)?;
+                for observer in observers {
+                    observer.game_finished(&result)?;
+                }
+                queue.finish(result);
+                pb.inc(1);
+            }
+            _ => return Err(protocol_error(&line).into()),
+        }
+    }
+}
+
+/// `ok`, or the kind of crash, of a game that ran for `duration`
+fn reported_status(status: ExitStatus, duration: Duration, timeout: Option<Duration>) -> String {
+    if status.success() {
+        "ok".to_owned()
+    } else {
+        // The workers don't enforce a --cpu-budget
+        CrashKind::of(status, timeout.is_some_and(|t| duration >= t), false).id()
+    }
+}
+
+/// Connects to a coordinator and plays the seeds it hands out with `game` until the run is over,
+/// killing the games that last longer than `timeout`
+pub fn work(coordinator: &str, game: &Path, timeout: Option<Duration>) -> Result<()> {
+    let stream = TcpStream::connect(coordinator)?;
+    keep_alive(&stream)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let writer = Mutex::new(stream);
+
+    let line = read_line(&mut reader)?;
+    let players: Vec<String> = match line.strip_prefix("players ") {
+        Some(players) => players.split_whitespace().map(str::to_owned).collect(),
+        None => return Err(protocol_error(&line).into()),
+    };
+    let line = read_line(&mut reader)?;
+    let settings = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["settings", len, via] => {
+            let via = SettingsVia::from_str(via, false).map_err(|_| protocol_error(&line))?;
+            Settings::new(read_payload(&mut reader, len)?, via)?
+        }
+        _ => return Err(protocol_error(&line).into()),
+    };
+    let line = read_line(&mut reader)?;
+    let count = match line.strip_prefix("env ") {
+        Some(count) => count.trim().parse().map_err(|_| protocol_error(&line))?,
+        None => return Err(protocol_error(&line).into()),
+    };
+    let env = (0..count)
+        .map(|_| {
+            let line = read_line(&mut reader)?;
+            match line.trim_end_matches('\n').split_once('=') {
+                Some((key, value)) => Ok((key.to_owned(), value.to_owned())),
+                None => Err(protocol_error(&line).into()),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let line = read_line(&mut reader)?;
+    let (limit, count) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["capture", limit, count] => (
+            limit.parse().map_err(|_| protocol_error(&line))?,
+            count.parse().map_err(|_| protocol_error(&line))?,
+        ),
+        _ => return Err(protocol_error(&line).into()),
+    };
+    let patterns = (0..count)
+        .map(|_| Ok(read_line(&mut reader)?.trim_end_matches('\n').to_owned()))
+        .collect::<Result<Vec<_>>>()?;
+    let capture = Capture::new(patterns, limit)?;
+    println!("Connected to {coordinator}, playing {}", players.join(", "));
+
+    let mut played = 0;
+    loop {
+        writeln!(
+            writer.lock().unwrap_or_else(PoisonError::into_inner),
+            "next"
+        )?;
+        let line = read_line(&mut reader)?;
+        let seeds: Vec<u32> = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["batch", ref seeds @ ..] => seeds
+                .iter()
+                .map(|s| s.parse().map_err(|_| protocol_error(&line)))
+                .collect::<Result<_, _>>()?,
+            ["wait"] => {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            ["done"] => break,
+            _ => return Err(protocol_error(&line).into()),
+        };
+
+        seeds.par_iter().try_for_each(|&seed| -> Result<()> {
+            let mut command = Command::new(game);
+            command
+                .args(&players)
+                .arg("-s")
+                .arg(seed.to_string())
+                .args(settings.args())
+                .envs(env.iter().map(|(k, v)| (k, v)));
+            let (status, output, usage) = runner::play(
+                &mut command,
+                &settings,
+                Stdio::null(),
+                timeout,
+                None,
+                &capture,
+            )?;
+
+            let micros = |d: Duration| d.as_micros().to_string();
+            let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_owned());
+            let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+            writeln!(
+                writer,
+                "result {seed} {} {} {} {} {} {}",
+                reported_status(status, usage.duration, timeout),
+                micros(usage.duration),
+                optional(usage.cpu.map(|c| micros(c.user))),
+                optional(usage.cpu.map(|c| micros(c.system))),
+                optional(usage.max_rss.map(|r| r.to_string())),
+                output.len()
+            )?;
+            writer.write_all(output.as_bytes())?;
+            Ok(())
+        })?;
+        played += seeds.len();
+    }
+
+    println!("Run finished, played {played} games");
+    Ok(())
+}
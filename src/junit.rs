@@ -0,0 +1,124 @@
+//! JUnit-style XML reports, which CI systems like Jenkins or GitLab render natively.
+
+use crate::metadata;
+use crate::parser::PlayerStatus;
+use crate::results::{GameRecord, TestResults};
+use crate::runner::CrashKind;
+use crate::TestConfig;
+
+use clap::ValueEnum;
+use itertools::Itertools;
+
+/// Outcome of the focal player that fails its game's test case, besides crashing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Failure {
+    /// It didn't get the highest score
+    Loss,
+    /// It ran out of CPU time
+    Timeout,
+    /// It got killed
+    Disqualified,
+    /// The game logged warnings about it
+    Warning,
+}
+
+impl Failure {
+    /// Why the game fails the test case, if it does
+    fn check(self, game: &GameRecord, seat: usize) -> Option<String> {
+        match self {
+            Self::Loss => game
+                .winners()
+                .filter(|winners| !winners[seat])
+                .map(|_| "Lost the game".to_owned()),
+            Self::Timeout => {
+                (game.status[seat] == PlayerStatus::TimedOut).then(|| "Timed out".to_owned())
+            }
+            Self::Disqualified => (game.status[seat] == PlayerStatus::Disqualified)
+                .then(|| "Got disqualified".to_owned()),
+            Self::Warning => {
+                (game.warnings[seat] > 0).then(|| format!("Got {} warnings", game.warnings[seat]))
+            }
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Report with a test case per game, failing the ones that crashed or where the focal player
+/// had one of the `fail_on` outcomes, and erroring the ones whose scores weren't found or that
+/// couldn't be started
+pub fn report(config: &TestConfig, results: &TestResults, fail_on: &[Failure]) -> String {
+    let seat = config.focus.unwrap_or(0);
+    let players = config.players.map(|p| p.as_string()).join(", ");
+    let classname = escape(&players);
+
+    let mut failures = 0;
+    let mut cases = Vec::new();
+    for game in results.games.iter().sorted_by_key(|g| g.seed) {
+        let problems: Vec<_> = match game.crash_kind {
+            None => fail_on.iter().filter_map(|f| f.check(game, seat)).collect(),
+            Some(kind) => vec![format!("The game crashed: {kind}")],
+        };
+        let open = format!(
+            "    <testcase name=\"seed {}\" classname=\"{classname}\" time=\"{:.3}\"",
+            game.seed,
+            game.usage.duration.as_secs_f64()
+        );
+        if problems.is_empty() {
+            cases.push(open + "/>");
+        } else {
+            failures += 1;
+            let kind = game
+                .crash_kind
+                .map_or_else(|| "threshold".to_owned(), CrashKind::id);
+            cases.push(format!(
+                "{open}>\n      <failure type=\"{kind}\" message=\"{}\"/>\n    </testcase>",
+                escape(&problems.join("; "))
+            ));
+        }
+    }
+    for seed in results.parse_error_seeds.iter().sorted() {
+        cases.push(format!(
+            "    <testcase name=\"seed {seed}\" classname=\"{classname}\">\n      <error message=\"The score regex didn't find every score\"/>\n    </testcase>"
+        ));
+    }
+    for seed in results.unstarted_seeds.iter().sorted() {
+        cases.push(format!(
+            "    <testcase name=\"seed {seed}\" classname=\"{classname}\">\n      <error message=\"The game couldn't be started\"/>\n    </testcase>"
+        ));
+    }
+    let errors = results.parse_error_seeds.len() + results.unstarted_seeds.len();
+
+    let time: f64 = results
+        .games
+        .iter()
+        .map(|g| g.usage.duration.as_secs_f64())
+        .sum();
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_owned(),
+        format!(
+            "<testsuites name=\"eda-game-tester\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">",
+            cases.len()
+        ),
+        format!(
+            "  <testsuite name=\"{classname}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">",
+            cases.len()
+        ),
+        "    <properties>".to_owned(),
+    ];
+    for (name, value) in metadata::properties(config) {
+        lines.push(format!(
+            "      <property name=\"{name}\" value=\"{}\"/>",
+            escape(&value).replace('\n', "&#10;")
+        ));
+    }
+    lines.push("    </properties>".to_owned());
+    lines.extend(cases);
+    lines.extend(["  </testsuite>".to_owned(), "</testsuites>".to_owned()]);
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,150 @@
+//! Standings kept across runs, for an ongoing league between a group of players.
+//!
+//! The league file has a line per player with its name, games played, total points, wins and
+//! rating, separated by tabs.
+
+use crate::errors::AppError;
+use crate::results::TestResults;
+
+use color_eyre::eyre::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Rating of a player that hasn't played yet
+const INITIAL_RATING: f64 = 1500.;
+/// Largest rating change from a game against a single opponent. Every game counts as a match
+/// against each of the 3 opponents, so it's split between them
+const K_FACTOR: f64 = 24.;
+
+struct Standing {
+    name: String,
+    games: u32,
+    points: u64,
+    wins: u32,
+    rating: f64,
+}
+
+pub struct League {
+    path: PathBuf,
+    standings: Vec<Standing>,
+}
+
+impl League {
+    /// Reads the league file, starting an empty league if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut standings = Vec::new();
+        if path.exists() {
+            let invalid = || AppError::InvalidLeagueFile(path.to_owned());
+            for line in fs::read_to_string(path)?.lines() {
+                if line.starts_with('#') || line.trim().is_empty() {
+                    continue;
+                }
+                let fields: Vec<_> = line.split('\t').collect();
+                let [name, games, points, wins, rating] = fields[..] else {
+                    return Err(invalid().into());
+                };
+                standings.push(Standing {
+                    name: name.to_owned(),
+                    games: games.parse().map_err(|_| invalid())?,
+                    points: points.parse().map_err(|_| invalid())?,
+                    wins: wins.parse().map_err(|_| invalid())?,
+                    rating: rating.parse().map_err(|_| invalid())?,
+                });
+            }
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            standings,
+        })
+    }
+
+    fn index(&mut self, name: &str) -> usize {
+        self.standings
+            .iter()
+            .position(|s| s.name == name)
+            .unwrap_or_else(|| {
+                self.standings.push(Standing {
+                    name: name.to_owned(),
+                    games: 0,
+                    points: 0,
+                    wins: 0,
+                    rating: INITIAL_RATING,
+                });
+                self.standings.len() - 1
+            })
+    }
+
+    /// Adds the games of a run, updating the ratings game by game in seed order
+    pub fn record(&mut self, players: &[String; 4], results: &TestResults) {
+        let seats = players.each_ref().map(|name| self.index(name));
+        let mut games: Vec<_> = results.games.iter().collect();
+        games.sort_by_key(|g| g.seed);
+
+        for points in games.iter().filter_map(|g| g.points) {
+            let max = points.iter().max().copied().unwrap_or_default();
+            let ratings = seats.map(|s| self.standings[s].rating);
+            for (seat, &index) in seats.iter().enumerate() {
+                // Pairwise Elo against every opponent, with ties as half a win
+                let mut change = 0.;
+                for other in (0..4).filter(|&o| o != seat) {
+                    let expected = 1. / (1. + 10_f64.powf((ratings[other] - ratings[seat]) / 400.));
+                    let actual = match points[seat].cmp(&points[other]) {
+                        std::cmp::Ordering::Greater => 1.,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Less => 0.,
+                    };
+                    change += K_FACTOR / 3. * (actual - expected);
+                }
+
+                let standing = &mut self.standings[index];
+                standing.games += 1;
+                standing.points += u64::from(points[seat]);
+                standing.wins += u32::from(points[seat] == max);
+                standing.rating += change;
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut lines = vec!["# name\tgames\tpoints\twins\trating".to_owned()];
+        lines.extend(self.standings.iter().map(|s| {
+            format!(
+                "{}\t{}\t{}\t{}\t{:.2}",
+                s.name, s.games, s.points, s.wins, s.rating
+            )
+        }));
+        fs::write(&self.path, lines.join("\n") + "\n")?;
+        Ok(())
+    }
+
+    /// Prints the standings, by rating
+    pub fn print(&self) {
+        if self.standings.is_empty() {
+            println!("No games recorded in {} yet", self.path.display());
+            return;
+        }
+
+        let mut standings: Vec<_> = self.standings.iter().collect();
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        println!(
+            "{:>4}  {:<20} {:>7} {:>6} {:>10} {:>8}",
+            "Rank", "Player", "Rating", "Games", "Avg score", "WR"
+        );
+        for (rank, s) in standings.iter().enumerate() {
+            let games = f64::from(s.games.max(1));
+            #[allow(clippy::cast_precision_loss)] // Correctness: Only used for display
+            let average = s.points as f64 / games;
+            println!(
+                "{:>4}  {:<20} {:>7.0} {:>6} {:>10.1} {:>7.1}%",
+                rank + 1,
+                s.name,
+                s.rating,
+                s.games,
+                average,
+                f64::from(s.wins) * 100. / games
+            );
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use color_eyre::eyre::Result;
+use std::{fs::File, io, path::Path, sync::Mutex};
+use tracing::Level;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+/// Sets up the log output.
+///
+/// Without a log file only warnings reach stderr, so logs don't get in the way of the progress
+/// bar. Each `-v` raises the verbosity one level (info, debug, trace), and a log file starts at
+/// the info level.
+pub fn init(verbosity: u8, log_file: Option<&Path>) -> Result<()> {
+    let level = match (verbosity, log_file.is_some()) {
+        (0, false) => Level::WARN,
+        (0 | 1, _) => Level::INFO,
+        (2, _) => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+
+    let writer = match log_file {
+        Some(path) => BoxMakeWriter::new(Mutex::new(File::create(path)?)),
+        None => BoxMakeWriter::new(io::stderr),
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(writer)
+        .with_target(false)
+        .init();
+
+    Ok(())
+}
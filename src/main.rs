@@ -1,25 +1,545 @@
 #![warn(clippy::pedantic)]
 
+mod artifacts;
+mod bench;
+mod bootstrap;
+mod build;
+mod champion;
+mod completions;
+mod config;
+mod cpu_budget;
+mod crash_report;
+mod dashboard;
+mod doctor;
 mod errors;
+mod flaky;
+mod focus;
+mod gauntlet;
+mod git;
+mod github;
+mod history;
+mod hooks;
+mod http;
+mod inspect;
+mod interesting;
+mod jobs;
+mod json;
+mod junit;
+mod league;
+mod logging;
+mod margins;
+mod metadata;
+mod metrics;
+mod notify;
+mod opponents;
+mod optimize;
+mod parser;
+mod plan;
+mod policy;
+mod progress;
+mod queue;
+mod regressions;
+mod remote;
+mod report;
+mod results;
+mod retention;
+mod runner;
+mod sandbox;
+mod seed_plan;
+mod signals;
+mod stream;
+mod sweep;
+mod teams;
+mod throttle;
+mod timing;
+mod tournament;
+mod tune;
+mod usage;
+mod viewer;
+mod watch;
+mod webhook;
+mod webui;
+mod workdir;
+use artifacts::RunArtifacts;
+use champion::Comparison;
+use completions::Shell;
+use config::{Lookahead, ProjectConfig};
+use cpu_budget::CpuBudget;
+use dashboard::Dashboard;
 use errors::AppError;
+use gauntlet::Gauntlet;
+use git::Revision;
+use hooks::Hooks;
+use jobs::ServeJobs;
+use league::League;
+use metrics::Metrics;
+use optimize::{Optimization, ParamRange};
+use policy::{FailOn, Policy};
+use progress::{InterimSummary, PlainProgress, ProgressEvents, SummaryEvery};
+use queue::Queue;
+use report::{Normalization, SortBy};
+use results::{OrderedResults, TestResults};
+use retention::{Compression, Retain, Retention};
+use runner::{GameObserver, GameRunner, SettingsVia};
+use sandbox::Sandbox;
+use seed_plan::{Layout, SeedPlan};
+use stream::ResultStream;
+use sweep::{Assignment, Combination};
+use teams::Teams;
+use throttle::Throttle;
+use tournament::Tournament;
+use tune::RankBy;
+use viewer::ViewerTarget;
+use webhook::WebhookFormat;
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use color_eyre::eyre::Result;
-use indicatif::ParallelProgressIterator;
 use indicatif::ProgressBar;
 use indicatif::ProgressStyle;
-use rayon::prelude::*;
-use regex::Regex;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::{
-    fs::File,
-    io::{Read, Write},
-    process::{Command, Stdio},
+    env, fs,
+    io::{self, IsTerminal, Write},
+    mem,
+    path::{self, Path, PathBuf},
+    sync::{
+        atomic::{self, AtomicBool, AtomicU32},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    time::Duration,
 };
 
 /// A simple tester for the EDA Game
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: Option<Args>,
+
+    /// Number of games run in parallel. Defaults to the number of CPUs
+    #[arg(short, long, global = true)]
+    jobs: Option<NonZeroUsize>,
+
+    /// Increase the log verbosity (repeatable)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Write the log (spawned commands, durations, exit status and parse warnings) to this file
+    #[arg(long, global = true, value_name = "FILE")]
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the tests, like when no subcommand is given
+    Run(Box<Args>),
+
+    /// Compare the results of two runs saved with --artifacts, over the seeds both played
+    Compare(CompareArgs),
+
+    /// Play a run again with the command line recorded in its manifest, crash report or any other
+    /// export with its metadata
+    Reproduce(ReproduceArgs),
+
+    /// Play every table of four players of a larger pool and rank the players
+    Tournament(TournamentArgs),
+
+    /// Play a player against mixes of three opponents drawn from a pool
+    Gauntlet(GauntletArgs),
+
+    /// List the runs saved with --artifacts
+    History(HistoryArgs),
+
+    /// Keep standings and ratings of the players across many runs
+    League(Box<LeagueArgs>),
+
+    /// Print the completion script for a shell
+    Completions(CompletionsArgs),
+
+    /// Measure the game throughput of this machine with the current --jobs setting
+    Bench(BenchArgs),
+
+    /// Run the tests for every combination of the swept --env and --setting values and rank them
+    Tune(Box<TuneArgs>),
+
+    /// Check that the game, settings and players are ready for a run, playing a single probe game
+    Doctor(Box<Args>),
+
+    /// Play the --seed of a run again on its own, with the game's output live in the terminal,
+    /// saving its replay
+    Inspect(Box<InspectArgs>),
+
+    /// Search for the best values of numeric parameters with SPSA, playing a new batch of
+    /// --instances seeds on each side of every step
+    Optimize(Box<OptimizeArgs>),
+
+    /// Run the tests on the workers that connect to this machine
+    ServeJobs(Box<ServeArgs>),
+
+    /// Play the games handed out by a `serve-jobs` coordinator
+    Worker(WorkerArgs),
+
+    /// Serve a local web UI to browse the saved runs, optionally following the progress of a new
+    /// one
+    Serve(Box<ServeWebArgs>),
+
+    /// Queue runs for `daemon` and list them
+    Queue(QueueArgs),
+
+    /// Play the queued runs in the background, one after the other or --parallel at a time,
+    /// saving them to the runs directory
+    Daemon(DaemonArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct InspectArgs {
+    /// File the replay is saved to, `SEED.res` by default
+    #[arg(long, value_name = "FILE")]
+    replay: Option<PathBuf>,
+
+    /// Open the replay in the --viewer once the game is over
+    #[arg(long)]
+    view: bool,
+
+    #[command(flatten)]
+    run: Args,
+}
+
+#[derive(clap::Args, Debug)]
+struct ServeWebArgs {
+    /// Address the web UI is served on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// Directory with the saved runs to browse (the `--artifacts` directory of the run, if
+    /// given)
+    #[arg(long, value_name = "DIR", default_value = "runs")]
+    runs_dir: PathBuf,
+
+    /// Let runs be queued from the web UI for `daemon` to play. Anyone who can reach the UI can
+    /// then play the game of this folder on this machine, with the options that don't run
+    /// commands or write files
+    #[arg(long)]
+    queue: bool,
+
+    #[command(subcommand)]
+    run: Option<ServeRun>,
+}
+
+#[derive(clap::Args, Debug)]
+struct QueueArgs {
+    /// Directory the queued runs are saved in, and the queue kept in
+    #[arg(long, value_name = "DIR", default_value = "runs")]
+    runs_dir: PathBuf,
+
+    #[command(subcommand)]
+    action: QueueAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum QueueAction {
+    /// Queue a run with these arguments, played from the current folder
+    Add {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        args: Vec<String>,
+    },
+
+    /// List the queued runs and how they went
+    List,
+}
+
+#[derive(clap::Args, Debug)]
+struct DaemonArgs {
+    /// Directory the queued runs are saved in, and the queue kept in
+    #[arg(long, value_name = "DIR", default_value = "runs")]
+    runs_dir: PathBuf,
+
+    /// Number of queued runs played at once
+    #[arg(long, default_value_t = NonZeroUsize::MIN)]
+    parallel: NonZeroUsize,
+
+    /// Number of games every queued run plays in parallel, instead of one per CPU
+    #[arg(long, value_name = "JOBS")]
+    run_jobs: Option<NonZeroUsize>,
+
+    /// Stop once the queue is empty instead of waiting for more runs
+    #[arg(long)]
+    until_empty: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum ServeRun {
+    /// Run the tests while serving, showing their progress live
+    Run(Args),
+}
+
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
+    /// Address the workers connect to
+    #[arg(long, default_value = "0.0.0.0:7878")]
+    listen: String,
+
+    /// Number of seeds handed to a worker at once
+    #[arg(long, default_value_t = NonZeroU32::new(10).unwrap())]
+    batch: NonZeroU32,
+
+    #[command(flatten)]
+    run: Args,
+}
+
+#[derive(clap::Args, Debug)]
+struct TuneArgs {
+    /// What the configurations are ranked by, for the focal player (player 1 by default)
+    #[arg(long, value_enum, default_value_t = RankBy::Score)]
+    rank_by: RankBy,
+
+    /// Export the results of every configuration to this CSV file
+    #[arg(long, value_name = "FILE")]
+    grid: Option<PathBuf>,
+
+    #[command(flatten)]
+    run: Args,
+}
+
+#[derive(clap::Args, Debug)]
+struct OptimizeArgs {
+    /// Environment variable to optimize within a range (repeatable). Integer bounds only produce
+    /// integer values
+    #[arg(short, long = "param", value_name = "KEY=MIN..MAX")]
+    params: Vec<ParamRange>,
+
+    /// Settings key to optimize within a range (repeatable)
+    #[arg(long = "setting-param", value_name = "KEY=MIN..MAX")]
+    setting_params: Vec<ParamRange>,
+
+    /// Number of SPSA iterations
+    #[arg(long, default_value_t = 50)]
+    iterations: u32,
+
+    /// What is maximized for the focal player (player 1 by default): its share of the points
+    /// scored, or its win rate
+    #[arg(long, value_enum, default_value_t = RankBy::Score)]
+    objective: RankBy,
+
+    /// Save the optimization state to this file after every iteration, resuming from it if it
+    /// already exists
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    #[command(flatten)]
+    run: Args,
+}
+
+#[derive(clap::Args, Debug)]
+struct WorkerArgs {
+    /// Address of the coordinator, as `host:port`
+    coordinator: String,
+
+    /// Game executable to run
+    #[arg(long, value_name = "PATH", default_value = runner::DEFAULT_GAME)]
+    game: PathBuf,
+
+    /// Kill the games that run for longer than this many seconds, counting them as crashed
+    #[arg(long, value_name = "SECONDS")]
+    game_timeout: Option<NonZeroU64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompareArgs {
+    /// Run directory the results are compared against
+    baseline: PathBuf,
+
+    /// Run directory with the new results
+    candidate: PathBuf,
+
+    /// Player whose placement drops are listed, the first one by default
+    #[arg(long, value_name = "PLAYER")]
+    focus: Option<String>,
+
+    /// Number of seeds listed where the player placed worse than in the baseline
+    #[arg(long, value_name = "COUNT", default_value_t = 10)]
+    drops: usize,
+
+    /// Save the replays of the listed seeds to this directory, playing them again with the game,
+    /// players and settings of the candidate run
+    #[arg(long, value_name = "DIR")]
+    save_drops: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReproduceArgs {
+    /// Export of the run, or its run directory
+    results: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct TournamentArgs {
+    /// Names of the players in the pool, at least 4
+    #[arg(required = true, num_args = 4..)]
+    players: Vec<String>,
+
+    /// Number of seeds played at every table
+    #[arg(short, long, default_value_t = NonZeroU32::new(20).unwrap())]
+    instances: NonZeroU32,
+
+    /// Initial seed of every table
+    #[arg(short, long, default_value_t = 0)]
+    seed: u32,
+
+    /// Play this many Swiss rounds instead of every table, pairing the players with similar
+    /// results so far
+    #[arg(long, value_name = "ROUNDS")]
+    swiss: Option<NonZeroU32>,
+
+    /// Print the tables and seeds that would be played, without playing them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Write the seeds played by every table to this JSON file, along with the overlaps and
+    /// gaps found in them
+    #[arg(long, value_name = "FILE")]
+    seed_plan: Option<PathBuf>,
+
+    /// Break the results of this player down by the opponents it shared the table with
+    #[arg(long, value_name = "PLAYER")]
+    focus: Option<String>,
+
+    /// Export the standings to this CSV file in the format of the official classification:
+    /// ranked by --placement-points, then by total score
+    #[arg(long, value_name = "FILE")]
+    classification: Option<PathBuf>,
+
+    /// Classification points of the first to the fourth place of every game, tied players
+    /// getting the points of the best place they share
+    #[arg(
+        long,
+        value_name = "POINTS",
+        value_delimiter = ',',
+        default_value = "3,2,1,0"
+    )]
+    placement_points: Vec<u32>,
+
+    /// Game settings file
+    #[arg(short, long, default_value_t = String::from("default.cnf"))]
+    game_settings: String,
+
+    /// Game executable to run
+    #[arg(long, value_name = "PATH", default_value = runner::DEFAULT_GAME)]
+    game: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct GauntletArgs {
+    /// Name of the player to test
+    player: String,
+
+    /// Names of the opponents to draw from, at least 3
+    #[arg(long, required = true, value_delimiter = ',', value_name = "PLAYERS")]
+    pool: Vec<String>,
+
+    /// Number of opponent mixes to draw, each playing its share of the seeds
+    #[arg(short, long, default_value_t = NonZeroU32::new(10).unwrap())]
+    mixes: NonZeroU32,
+
+    /// Number of seeds to play, split between the mixes
+    #[arg(short, long, default_value_t = NonZeroU32::new(100).unwrap())]
+    instances: NonZeroU32,
+
+    /// Print the mixes and seeds that would be played, without playing them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Write the seeds played by every mix to this JSON file, along with the overlaps and
+    /// gaps found in them
+    #[arg(long, value_name = "FILE")]
+    seed_plan: Option<PathBuf>,
+
+    /// Initial seed
+    #[arg(short, long, default_value_t = 0)]
+    seed: u32,
+
+    /// Game settings file
+    #[arg(short, long, default_value_t = String::from("default.cnf"))]
+    game_settings: String,
+
+    /// Game executable to run
+    #[arg(long, value_name = "PATH", default_value = runner::DEFAULT_GAME)]
+    game: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct HistoryArgs {
+    /// Directory the runs were saved in
+    #[arg(default_value = "runs")]
+    dir: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct LeagueArgs {
+    /// File the standings are kept in
+    #[arg(long, value_name = "FILE", default_value = "league.txt")]
+    file: PathBuf,
+
+    #[command(subcommand)]
+    action: LeagueAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum LeagueAction {
+    /// Run the tests and add their games to the standings
+    Play(Box<Args>),
+
+    /// Print the standings
+    Standings,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompletionsArgs {
+    #[arg(value_enum)]
+    shell: Shell,
+}
+
+#[derive(clap::Args, Debug)]
+struct BenchArgs {
+    /// Name of player 1
+    player1: String,
+
+    /// Name of player 2
+    player2: String,
+
+    /// Name of player 3
+    player3: String,
+
+    /// Name of player 4
+    player4: String,
+
+    /// Number of seeds in the benchmark set
+    #[arg(short, long, default_value_t = NonZeroU32::new(20).unwrap())]
+    instances: NonZeroU32,
+
+    /// Initial seed of the benchmark set
+    #[arg(short, long, default_value_t = 0)]
+    seed: u32,
+
+    /// Times the whole seed set is played
+    #[arg(short, long, default_value_t = NonZeroU32::new(5).unwrap())]
+    runs: NonZeroU32,
+
+    /// Game settings file
+    #[arg(short, long, default_value_t = String::from("default.cnf"))]
+    game_settings: String,
+
+    /// Game executable to run
+    #[arg(long, value_name = "PATH", default_value = runner::DEFAULT_GAME)]
+    game: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+#[allow(clippy::struct_excessive_bools)] // Correctness: These are independent CLI flags
 struct Args {
     /// Name of player 1
     player1: String,
@@ -37,13 +557,368 @@ struct Args {
     #[arg(short, long, default_value_t = NonZeroU32::new(100).unwrap())]
     instances: NonZeroU32,
 
+    /// Fill the four seats with this player, reporting how much its scores vary between seats
+    /// and its crash rate: a quick check that it doesn't crash and plays the same from any seat
+    #[arg(long, value_name = "PLAYER", conflicts_with = "player1")]
+    mirror: Option<String>,
+
+    /// Players of seats 2 to 4 when they're left out, by seat, starting over if there are fewer
+    /// than three
+    #[arg(
+        long,
+        value_name = "PLAYERS",
+        value_delimiter = ',',
+        default_value = "Dummy"
+    )]
+    fill: Vec<String>,
+
+    /// Split the seats in two teams, giving the seats of the first one (like `1,2`). The summary
+    /// then reports the teams' combined scores, the highest deciding the winner
+    #[arg(long, value_name = "SEATS")]
+    teams: Option<Teams>,
+
+    /// Take the players and options of this preset of the configuration file. Several presets
+    /// (repeated or comma-separated) are run one after the other and summarized together
+    #[arg(long, value_name = "NAME", value_delimiter = ',')]
+    preset: Vec<String>,
+
+    /// Copy this player source into the game folder and rebuild the game with `make` before
+    /// testing, checking that the player got registered (repeatable)
+    #[arg(long, value_name = "FILE")]
+    player_src: Vec<PathBuf>,
+
+    /// Keep running: rebuild the game and play the seeds again every time this source changes
+    /// (repeatable), comparing the results with the previous build
+    #[arg(long, value_name = "FILE")]
+    watch: Vec<PathBuf>,
+
+    /// Run the games on these machines over SSH instead of locally. The game binary is copied to
+    /// each of them, and --jobs sets the total number of games in flight across all of them
+    #[arg(long, value_name = "HOST,...", value_delimiter = ',')]
+    workers: Vec<String>,
+
+    /// Run every game inside a sandbox: `bwrap` for a read-only filesystem and no network with
+    /// bubblewrap, or a wrapper command (like `firejail --quiet`) the game command line is
+    /// appended to
+    #[arg(long, value_name = "bwrap|COMMAND")]
+    sandbox: Option<Sandbox>,
+
+    /// Run every game in its own temporary directory, linking the files of the current one, so
+    /// the files written by parallel games don't clash
+    #[arg(long)]
+    isolate: bool,
+
+    /// Set an environment variable for the games (repeatable). A comma-separated list of values
+    /// runs the tests once for each of them, combining the values of every swept variable
+    #[arg(short, long, value_name = "KEY=VALUE[,VALUE...]")]
+    env: Vec<Assignment>,
+
+    /// Override a `KEY value` line of the game settings (repeatable). Like --env, a
+    /// comma-separated list of values sweeps over them
+    #[arg(long = "setting", value_name = "KEY=VALUE[,VALUE...]")]
+    settings: Vec<Assignment>,
+
     /// Initial seed to test
     #[arg(short, long, default_value_t = 0)]
     seed: u32,
 
+    /// Times each seed is played, reporting the seeds whose outcome changed between repetitions
+    #[arg(long, default_value_t = NonZeroU32::new(1).unwrap())]
+    repeat: NonZeroU32,
+
     /// Game settings file
     #[arg(short, long, default_value_t = String::from("default.cnf"))]
     game_settings: String,
+
+    /// Game executable to run. Given several times, the same players and seeds are run with
+    /// every one of them (like last year's build and the updated one), reporting each apart
+    #[arg(long, value_name = "PATH", default_value = runner::DEFAULT_GAME)]
+    game: Vec<PathBuf>,
+
+    /// Frozen build of the game with the best known version of the focal player. Its seeds are
+    /// played with it too, reporting whether the current build beats it and offering to make the
+    /// current build the new champion when it does
+    #[arg(long, value_name = "PATH")]
+    champion: Option<PathBuf>,
+
+    /// Make the current build the new --champion without asking when it beats it
+    #[arg(long, requires = "champion")]
+    promote: bool,
+
+    /// How the game gets its settings: written to its stdin once it starts, as a file given with
+    /// `-i FILE`, or with its stdin redirected from a file. Newer builds of the game may need one
+    /// of the files, which can be set for the project in the configuration file
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = SettingsVia::Stdin)]
+    settings_via: SettingsVia,
+
+    /// Kill the games that run for longer than this many seconds, counting them as crashed
+    #[arg(long, value_name = "SECONDS")]
+    game_timeout: Option<NonZeroU64>,
+
+    /// CPU seconds the judge lets a game use. The games going over it once scaled by
+    /// --judge-slowdown are reported, as the judge could disqualify the players in them even
+    /// though they finished here
+    #[arg(long, value_name = "SECONDS")]
+    cpu_budget: Option<f64>,
+
+    /// How many times slower the judge's machine is than this one, for --cpu-budget
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.)]
+    judge_slowdown: f64,
+
+    /// Kill the games once they go over the --cpu-budget, as the judge would, by limiting the CPU
+    /// time of the game process. They count as crashed
+    #[arg(long, requires = "cpu_budget")]
+    enforce_cpu_budget: bool,
+
+    /// Bytes of every game's stderr kept besides the lines the score, status, timeline and
+    /// metric regexes match, which are always kept. Only matters for the crash dumps, and to
+    /// bound the memory used by very verbose players
+    #[arg(long, value_name = "BYTES", default_value_t = runner::DEFAULT_STDERR_LIMIT)]
+    stderr_limit: usize,
+
+    /// Times to retry starting a game that fails because the system is short of processes,
+    /// memory or file descriptors, waiting longer every time. Games that still can't start are
+    /// reported apart from the crashes
+    #[arg(long, value_name = "RETRIES", default_value_t = runner::DEFAULT_SPAWN_RETRIES)]
+    spawn_retries: u32,
+
+    /// Regex used to find the players' scores in the game output. The score is read from the
+    /// `score` named group (or the first group), and the optional `name` group assigns it to the
+    /// player with that name instead of relying on output order
+    #[arg(long, default_value_t = String::from(parser::DEFAULT_SCORE_REGEX))]
+    score_regex: String,
+
+    /// Regex matching the line logged when a player exceeds its CPU time. Needs a `name` group
+    #[arg(long, default_value_t = String::from(parser::DEFAULT_TIMEOUT_REGEX))]
+    timeout_regex: String,
+
+    /// Regex matching the line logged when a player is killed. Needs a `name` group
+    #[arg(long, default_value_t = String::from(parser::DEFAULT_DISQUALIFIED_REGEX))]
+    disqualified_regex: String,
+
+    /// Regex matching the lines logged when the game rejects something a player did, like an
+    /// illegal command or an invalid move. Needs a `name` group
+    #[arg(long, default_value_t = String::from(parser::DEFAULT_WARNING_REGEX))]
+    warning_regex: String,
+
+    /// Collect the intermediate round scores and report the average score trajectory
+    #[arg(long)]
+    timeline: bool,
+
+    /// Regex matching the start of a round, used with --timeline
+    #[arg(long, default_value_t = String::from(parser::DEFAULT_ROUND_REGEX))]
+    round_regex: String,
+
+    /// Regex matching a player's score during a round, used with --timeline. Needs `name` and
+    /// `score` groups
+    #[arg(long, default_value_t = String::from(parser::DEFAULT_ROUND_SCORE_REGEX))]
+    round_score_regex: String,
+
+    /// Extra regex to extract per-player metrics (repeatable). Needs a `name` group, and every
+    /// other named group is aggregated as a metric
+    #[arg(short, long = "metric")]
+    metrics: Vec<String>,
+
+    /// Save the game output (the board stream read by the viewer) of every seed in this folder
+    #[arg(long, value_name = "DIR")]
+    save_output: Option<PathBuf>,
+
+    /// Store the configuration, summary, per-seed results, crash dumps and replays of this run
+    /// in a timestamped folder inside this directory
+    #[arg(long, value_name = "DIR")]
+    artifacts: Option<PathBuf>,
+
+    /// Replays left once the run is over, in --save-output or the --artifacts folder. The crash
+    /// dumps are always kept
+    #[arg(long, value_enum, value_name = "WHICH", default_value_t = Retain::All)]
+    retain: Retain,
+
+    /// Interesting games whose replays are kept with --retain interesting
+    #[arg(long, value_name = "GAMES", default_value_t = 10)]
+    retain_count: usize,
+
+    /// Largest size of the replays and crash dumps of the run, removing the least interesting
+    /// ones until they fit
+    #[arg(long, value_name = "MIB")]
+    max_artifacts_size: Option<u64>,
+
+    /// Write a JSON report of the crashes, games whose scores weren't found, games that couldn't
+    /// start, timeouts and disqualifications to this file if there were any. It's always saved
+    /// with --artifacts
+    #[arg(long, value_name = "FILE")]
+    crash_report: Option<PathBuf>,
+
+    /// Write the seeds played by every configuration of a sweep to this JSON file, along with the
+    /// overlaps and gaps found in them
+    #[arg(long, value_name = "FILE")]
+    seed_plan: Option<PathBuf>,
+
+    /// Compress the replays and crash dumps once the run is over, with the `gzip` or `zstd`
+    /// command
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    compress: Option<Compression>,
+
+    /// Write one JSON line per finished game to this file (or `-` for stdout) as the run
+    /// progresses
+    #[arg(long, value_name = "FILE")]
+    stream: Option<PathBuf>,
+
+    /// Write machine-readable progress events (one JSON object per line) to this file
+    #[arg(long, value_name = "FILE")]
+    progress_json: Option<PathBuf>,
+
+    /// Shell command run before every game, with the seed as its first argument and in
+    /// `EDA_SEED`, and the players in `EDA_PLAYERS`
+    #[arg(long, value_name = "COMMAND")]
+    pre_game: Option<String>,
+
+    /// Shell command run after every game, like --pre-game, also getting `EDA_STATUS`
+    /// (ok|crash|parse-error|unstarted), `EDA_SCORES`, `EDA_WINNERS`, `EDA_DURATION_MS`, and the
+    /// paths of the saved output and crash dump in `EDA_OUTPUT` and `EDA_CRASH_DUMP`
+    #[arg(long, value_name = "COMMAND")]
+    post_game: Option<String>,
+
+    /// Serve Prometheus metrics (games finished, crashes, throughput and running win rates) at
+    /// `/metrics` on this address while the tests run
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<String>,
+
+    /// Show running averages, win rates and crash count below the progress bar
+    #[arg(long)]
+    dashboard: bool,
+
+    /// Print periodic progress lines instead of a progress bar. This is the default when the
+    /// progress isn't shown on a terminal
+    #[arg(long)]
+    plain: bool,
+
+    /// Hide the progress and print only a line per player with its name, average score and win
+    /// rate in percent, separated by tabs, and then `crashed` and the number of crashed games, for
+    /// scripts. Everything else goes to stderr
+    #[arg(long)]
+    summary_only: bool,
+
+    /// Print the running averages, win rates and crashes every this many finished games
+    #[arg(long, value_name = "GAMES")]
+    summary_every: Option<NonZeroU32>,
+
+    /// Print the running averages, win rates and crashes every this many seconds, checked as
+    /// games finish
+    #[arg(long, value_name = "SECONDS")]
+    summary_interval: Option<NonZeroU64>,
+
+    /// Write the running statistics to this file when the process gets `SIGUSR1`, instead of
+    /// printing them
+    #[arg(long, value_name = "FILE")]
+    stats_file: Option<PathBuf>,
+
+    /// After the run, save the output of this seed (or the `closest` game) and open it in the
+    /// viewer
+    #[arg(long, value_name = "SEED|closest")]
+    open_viewer: Option<ViewerTarget>,
+
+    /// Path to the HTML viewer used by --open-viewer
+    #[arg(long, default_value = "Viewer/viewer.html")]
+    viewer: PathBuf,
+
+    /// Save the output of the notable games of the run (closest game, biggest upset and the
+    /// focal player's worst loss)
+    #[arg(long)]
+    save_interesting: bool,
+
+    /// Show a desktop notification with the headline result when the run finishes (or ring the
+    /// terminal bell if notifications aren't available)
+    #[arg(long)]
+    notify: bool,
+
+    /// Write an XML report in the `JUnit` format to this file, with a test case per game that
+    /// fails if the game crashed
+    #[arg(long, value_name = "PATH")]
+    junit: Option<PathBuf>,
+
+    /// Also fail the `JUnit` test case of a game on these outcomes of the focal player
+    /// (comma-separated)
+    #[arg(long, value_enum, value_name = "OUTCOMES", value_delimiter = ',')]
+    junit_fail_on: Vec<junit::Failure>,
+
+    /// POST the summary of the run to this URL when it finishes
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// Payload posted by --webhook
+    #[arg(long, value_enum, default_value_t = WebhookFormat::Json)]
+    webhook_format: WebhookFormat,
+
+    /// Focal player: list the seeds where it did worst. Defaults to player 1 for other reports
+    #[arg(long, value_name = "PLAYER")]
+    focus: Option<String>,
+
+    /// Number of seeds listed by --focus
+    #[arg(long, default_value_t = 10)]
+    focus_count: usize,
+
+    /// Order of the players in the summary: `wr`, `score`, `seat`, `top` (the --top rate), `norm`
+    /// (the --normalize score) or the name of a --metric group, highest first
+    #[arg(long, value_name = "KEY", default_value = "wr")]
+    sort_by: String,
+
+    /// Places of a table counting as a good finish for the top-K rate of the summary, as in
+    /// tournaments where the top half of every table advances
+    #[arg(long, value_name = "K", default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..4))]
+    top: u8,
+
+    /// Also average the scores relative to the rest of their game, so that a few blowout games
+    /// don't dominate the averages
+    #[arg(long, value_enum, value_name = "MODE")]
+    normalize: Option<Normalization>,
+
+    /// What makes the process exit with an error once the run is over
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = FailOn::Never)]
+    fail_on: FailOn,
+
+    /// Run saved with --artifacts that --fail-on regression compares with. The seeds where the
+    /// focal player placed worse than in it get listed too
+    #[arg(long, value_name = "DIR")]
+    baseline: Option<PathBuf>,
+
+    /// Number of seeds listed where the focal player placed worse than in the --baseline run, or
+    /// than with the previous build with --watch
+    #[arg(long, value_name = "COUNT", default_value_t = 10)]
+    drops: usize,
+
+    /// Save the replays of the listed seeds where the focal player placed worse than in the
+    /// --baseline run to this directory
+    #[arg(long, value_name = "DIR")]
+    save_drops: Option<PathBuf>,
+
+    /// Percentage points the focal player's win rate can drop from the baseline before it counts
+    /// as a regression
+    #[arg(long, value_name = "POINTS", default_value_t = 1.)]
+    max_regression: f64,
+
+    /// Lowest win rate of the focal player, in percent, that passes --fail-on thresholds
+    #[arg(long, value_name = "PERCENT")]
+    min_win_rate: Option<f64>,
+
+    /// Lowest average score of the focal player that passes --fail-on thresholds
+    #[arg(long, value_name = "POINTS")]
+    min_score: Option<f64>,
+
+    /// Start fewer games at once when they, along with the rest of the system, would keep more
+    /// than this many CPUs busy, so the players don't run out of CPU time because of the others.
+    /// The CPUs a game uses are measured as they finish, as the game may run several threads
+    #[arg(long, value_name = "CPUS")]
+    load_target: Option<f64>,
+
+    /// Stop the run at the first game that crashes, couldn't be started or had a player time out
+    /// or get disqualified, killing the games still running, and print the seed to replay it
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Print the commands, seeds, settings, parallelism and outputs of the run, without building
+    /// or playing anything
+    #[arg(long)]
+    dry_run: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -74,171 +949,1292 @@ impl TryFrom<&str> for PlayerName {
     }
 }
 
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)] // Correctness: These are independent options
 struct TestConfig {
     seed: u32,
     instances: NonZeroU32,
+    repeat: NonZeroU32,
     players: [PlayerName; 4],
     settings_file: String,
+    game: PathBuf,
+    game_timeout: Option<Duration>,
+    cpu_budget: Option<CpuBudget>,
+    stderr_limit: usize,
+    settings_via: SettingsVia,
+    spawn_retries: u32,
+    workers: Vec<String>,
+    serve_jobs: Option<ServeJobs>,
+    sandbox: Option<Sandbox>,
+    isolate: bool,
+    env: Vec<(String, String)>,
+    settings: Vec<(String, String)>,
+    /// Don't print the summary, as the caller reports the results itself
+    quiet: bool,
+    revision: Option<Revision>,
+    score_regex: String,
+    timeout_regex: String,
+    disqualified_regex: String,
+    warning_regex: String,
+    timeline: Option<(String, String)>,
+    metrics: Vec<String>,
+    save_output: Option<PathBuf>,
+    artifacts: Option<PathBuf>,
+    /// What's left of the replays and crash dumps
+    retention: Retention,
+    crash_report: Option<PathBuf>,
+    /// File the seeds of every configuration of a sweep are written to
+    seed_plan: Option<PathBuf>,
+    stream: Option<PathBuf>,
+    progress_json: Option<PathBuf>,
+    pre_game: Option<String>,
+    post_game: Option<String>,
+    metrics_addr: Option<String>,
+    /// Statistics shown by the web UI
+    live: Option<Arc<Metrics>>,
+    dashboard: bool,
+    plain: bool,
+    summary_only: bool,
+    /// When to print the interim summaries
+    summary_every: SummaryEvery,
+    /// Where the statistics asked for with `SIGUSR1` go, instead of stderr
+    stats_file: Option<PathBuf>,
+    open_viewer: Option<ViewerTarget>,
+    viewer: PathBuf,
+    save_interesting: bool,
+    notify: bool,
+    webhook: Option<(String, WebhookFormat)>,
+    focus: Option<usize>,
+    focus_count: usize,
+    /// Seeds listed where the focal player placed worse than before, and where to save them
+    drops: usize,
+    save_drops: Option<PathBuf>,
+    /// `JUnit` report to write, and the outcomes failing its test cases besides crashes
+    junit: Option<(PathBuf, Vec<junit::Failure>)>,
+    /// What makes the run fail
+    policy: Policy,
+    /// Stop at the first failed game
+    fail_fast: bool,
+    /// CPUs the local games and the rest of the system may keep busy
+    load_target: Option<f64>,
+    /// All four seats hold the same player
+    mirror: bool,
+    teams: Option<Teams>,
+    sort_by: SortBy,
+    /// Finishing places counted by the top-K rate
+    top: u8,
+    /// Normalized score shown next to the average
+    normalize: Option<Normalization>,
+    /// Print the plan of the run instead of playing it
+    dry_run: bool,
+}
+
+impl TestConfig {
+    /// Configuration with every optional feature turned off
+    fn new(players: [PlayerName; 4], settings_file: String, game: PathBuf) -> Self {
+        Self {
+            seed: 0,
+            instances: NonZeroU32::new(100).unwrap(),
+            repeat: NonZeroU32::new(1).unwrap(),
+            players,
+            settings_file,
+            game,
+            game_timeout: None,
+            cpu_budget: None,
+            stderr_limit: runner::DEFAULT_STDERR_LIMIT,
+            settings_via: SettingsVia::Stdin,
+            spawn_retries: runner::DEFAULT_SPAWN_RETRIES,
+            workers: Vec::new(),
+            serve_jobs: None,
+            sandbox: None,
+            isolate: false,
+            env: Vec::new(),
+            settings: Vec::new(),
+            quiet: false,
+            revision: Revision::current(),
+            score_regex: parser::DEFAULT_SCORE_REGEX.to_owned(),
+            timeout_regex: parser::DEFAULT_TIMEOUT_REGEX.to_owned(),
+            disqualified_regex: parser::DEFAULT_DISQUALIFIED_REGEX.to_owned(),
+            warning_regex: parser::DEFAULT_WARNING_REGEX.to_owned(),
+            timeline: None,
+            metrics: Vec::new(),
+            save_output: None,
+            artifacts: None,
+            retention: Retention::default(),
+            crash_report: None,
+            seed_plan: None,
+            stream: None,
+            progress_json: None,
+            pre_game: None,
+            post_game: None,
+            metrics_addr: None,
+            live: None,
+            dashboard: false,
+            plain: !io::stderr().is_terminal(),
+            summary_only: false,
+            summary_every: SummaryEvery::default(),
+            stats_file: None,
+            open_viewer: None,
+            viewer: PathBuf::new(),
+            save_interesting: false,
+            notify: false,
+            webhook: None,
+            focus: None,
+            focus_count: 0,
+            drops: 0,
+            save_drops: None,
+            junit: None,
+            policy: Policy::default(),
+            fail_fast: false,
+            load_target: None,
+            mirror: false,
+            teams: None,
+            sort_by: SortBy::WinRate,
+            top: 2,
+            normalize: None,
+            dry_run: false,
+        }
+    }
+
+    /// Human-readable dump of the resolved configuration
+    fn describe(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "git_revision = {:?}",
+                self.revision
+                    .as_ref()
+                    .map_or_else(String::new, ToString::to_string)
+            ),
+            format!("seed = {}", self.seed),
+            format!("instances = {}", self.instances),
+            format!("repeat = {}", self.repeat),
+            format!(
+                "players = [{}]",
+                self.players
+                    .map(|p| format!("{:?}", p.as_string()))
+                    .join(", ")
+            ),
+            format!("settings_file = {:?}", self.settings_file),
+            format!("game = {:?}", self.game),
+            format!("game_timeout = {:?}", self.game_timeout),
+            format!("cpu_budget = {:?}", self.cpu_budget),
+            format!("stderr_limit = {}", self.stderr_limit),
+            format!("settings_via = {:?}", self.settings_via.name()),
+            format!("spawn_retries = {}", self.spawn_retries),
+            format!("workers = {:?}", self.workers),
+            format!("sandbox = {:?}", self.sandbox),
+            format!("isolate = {}", self.isolate),
+            format!("env = {:?}", sweep::describe(&self.env)),
+            format!("settings = {:?}", sweep::describe(&self.settings)),
+            format!("score_regex = {:?}", self.score_regex),
+            format!("timeout_regex = {:?}", self.timeout_regex),
+            format!("disqualified_regex = {:?}", self.disqualified_regex),
+            format!("warning_regex = {:?}", self.warning_regex),
+        ];
+        if let Some((round, score)) = &self.timeline {
+            lines.push(format!("round_regex = {round:?}"));
+            lines.push(format!("round_score_regex = {score:?}"));
+        }
+        for metric in &self.metrics {
+            lines.push(format!("metric = {metric:?}"));
+        }
+        lines.join("\n") + "\n"
+    }
 }
 
 fn main() -> Result<()> {
     color_eyre::install()?;
 
-    let args = Args::parse();
+    // The defaults of the configuration file are filled in before parsing, so the flags
+    // override them
+    let project = ProjectConfig::load()?;
+    let lookahead = project.lookahead(Cli::command())?;
+    let matches = cli_command(
+        &project,
+        lookahead.presets.first().map(String::as_str),
+        &lookahead,
+    )?
+    .get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    logging::init(cli.verbose, cli.log_file.as_deref())?;
+    signals::install()?;
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs.get())
+            .build_global()?;
+    }
+
+    // Without any option, the run can still be fully configured by the configuration file
+    let run_args = cli.run.or_else(|| Args::from_arg_matches(&matches).ok());
+    match (cli.command, run_args) {
+        (Some(Command::Bench(args)), _) => {
+            let players = parse_players([args.player1, args.player2, args.player3, args.player4]);
+            let mut config =
+                TestConfig::new(players, args.game_settings, path::absolute(&args.game)?);
+            config.seed = args.seed;
+            config.instances = args.instances;
+            bench::run(&config, args.runs)
+        }
+        (Some(Command::Doctor(args)), _) => {
+            doctor::run(&test_config(*args)?, rayon::current_num_threads())
+        }
+        (Some(Command::Inspect(args)), _) => {
+            let InspectArgs { replay, view, run } = *args;
+            inspect::run(&test_config(run)?, replay, view)
+        }
+        (Some(Command::Tune(mut args)), _) => {
+            let sweep = Sweep::take(&mut args.run);
+            let mut config = test_config(args.run)?;
+            config.quiet = true;
+            let results = run_sweep(&config, &sweep)?;
+            if config.dry_run {
+                return Ok(());
+            }
+            tune::report(
+                &config,
+                &sweep,
+                &results,
+                args.rank_by,
+                args.grid.as_deref(),
+            )
+        }
+        (Some(Command::Optimize(mut args)), _) => {
+            let sweep = Sweep::take(&mut args.run);
+            let mut config = test_config(args.run)?;
+            sweep.apply_first(&mut config);
+            if config.dry_run {
+                return run_tests(&config).map(drop);
+            }
+            Optimization {
+                env: args.params,
+                settings: args.setting_params,
+                iterations: args.iterations,
+                objective: args.objective,
+                checkpoint: args.checkpoint,
+            }
+            .run(&config)
+        }
+        (Some(Command::ServeJobs(args)), _) => serve_jobs(*args),
+        (Some(Command::Worker(args)), _) => jobs::work(
+            &args.coordinator,
+            &path::absolute(&args.game)?,
+            args.game_timeout.map(|t| Duration::from_secs(t.get())),
+        ),
+        (Some(Command::Serve(args)), _) => serve_web(*args),
+        (Some(Command::Queue(args)), _) => queue(args),
+        (Some(Command::Daemon(args)), _) => queue::daemon(
+            &args.runs_dir,
+            args.parallel,
+            args.run_jobs,
+            args.until_empty,
+        ),
+        (Some(Command::Run(args)), _) => run(*args, &project),
+        (Some(Command::Compare(args)), _) => compare(&args),
+        (Some(Command::Reproduce(args)), _) => metadata::reproduce(&args.results),
+        (Some(Command::Tournament(args)), _) => tournament(args),
+        (Some(Command::Gauntlet(args)), _) => gauntlet(args),
+        (Some(Command::History(args)), _) => history::list(&args.dir),
+        (Some(Command::League(args)), _) => league(*args),
+        (Some(Command::Completions(args)), _) => {
+            print!("{}", completions::generate(Cli::command(), args.shell));
+            Ok(())
+        }
+        (None, Some(args)) => run(args, &project),
+        (None, None) => {
+            Cli::command().print_help()?;
+            Ok(())
+        }
+    }
+}
+
+/// Definition of the command line, with the defaults of the configuration file, `preset` and
+/// the seats given by `--fill` and `--mirror`
+fn cli_command(
+    project: &ProjectConfig,
+    preset: Option<&str>,
+    lookahead: &Lookahead,
+) -> Result<clap::Command> {
+    let command = config::fill(project.apply(Cli::command(), preset)?, &lookahead.fill);
+    Ok(match lookahead.mirror.as_deref() {
+        Some(player) => config::mirror(command, player),
+        None => command,
+    })
+}
+
+/// Where the output of a run meant for people goes: stderr with --summary-only, so that stdout
+/// only gets the summary lines
+fn human_output(summary_only: bool) -> Box<dyn Write> {
+    if summary_only {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
+/// Runs the tests, once for every preset given, failing at the end if any of them broke the
+/// --fail-on policy
+fn run(args: Args, project: &ProjectConfig) -> Result<()> {
+    let mut failures = Vec::new();
+    if args.preset.is_empty() {
+        let players =
+            [&args.player1, &args.player2, &args.player3, &args.player4].map(Clone::clone);
+        let several_games = args.game.len() > 1 || args.champion.is_some();
+        let (dry_run, summary_only) = (args.dry_run, args.summary_only);
+        let lineup;
+        (lineup, failures) = run_lineup(args)?;
+        if several_games && !dry_run {
+            let groups: Vec<_> = lineup
+                .into_iter()
+                .map(|(label, results)| (label.trim_start().to_owned(), players.clone(), results))
+                .collect();
+            report::write_groups(&mut human_output(summary_only), &groups)?;
+        }
+    } else {
+        run_presets(&args, project, &mut failures)?;
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::RunFailed(failures.join("; ")).into())
+    }
+}
+
+fn run_presets(args: &Args, project: &ProjectConfig, failures: &mut Vec<String>) -> Result<()> {
+    let lookahead = Lookahead {
+        presets: Vec::new(),
+        mirror: args.mirror.clone(),
+        fill: args.fill.clone(),
+    };
+    let mut out = human_output(args.summary_only);
+    let mut groups = Vec::new();
+    for name in &args.preset {
+        writeln!(out, "Preset {name}:")?;
+        // Parsed again, so the flags override the preset like they override the file defaults
+        let matches = cli_command(project, Some(name), &lookahead)?.get_matches();
+        let matches = matches.subcommand_matches("run").unwrap_or(&matches);
+        let mut preset = Args::from_arg_matches(matches)?;
+        preset.preset.clear();
+
+        let players = [
+            &preset.player1,
+            &preset.player2,
+            &preset.player3,
+            &preset.player4,
+        ]
+        .map(Clone::clone);
+        let (lineup, lineup_failures) = run_lineup(preset)?;
+        failures.extend(lineup_failures.into_iter().map(|f| format!("{name}: {f}")));
+        for (label, results) in lineup {
+            groups.push((format!("{name}{label}"), players.clone(), results));
+        }
+        writeln!(out)?;
+    }
 
-    let config = TestConfig {
+    if groups.len() > 1 && !args.dry_run {
+        report::write_groups(&mut out, &groups)?;
+    }
+    Ok(())
+}
+
+/// Runs the tests, once for every game and combination of the swept values or every time a
+/// watched file changes. Returns the results of every combination, with its game if there are
+/// several and its description if it's a sweep, and the reasons the run fails the --fail-on
+/// policy
+type Lineup = (Vec<(String, TestResults)>, Vec<String>);
+fn run_lineup(mut args: Args) -> Result<Lineup> {
+    let sweep = Sweep::take(&mut args);
+    let watched = mem::take(&mut args.watch);
+    let (champion, promote) = (args.champion.take(), args.promote);
+    let mut games = args.game.clone();
+    games.extend(champion.clone());
+    let mut config = test_config(args)?;
+    if watched.is_empty() || config.dry_run {
+        let is_sweep = sweep::is_sweep(&sweep.env, &sweep.settings);
+        let mut lineup = Vec::new();
+        let mut failures = Vec::new();
+        for game in &games {
+            let mut label = String::new();
+            if games.len() > 1 {
+                writeln!(
+                    human_output(config.summary_only),
+                    "Game {}:",
+                    game.display()
+                )?;
+                config.game = path::absolute(game)?;
+                label = format!(" [{}]", game.display());
+            }
+            for (combination, results) in run_sweep(&config, &sweep)? {
+                let label = if is_sweep {
+                    format!(
+                        "{label} ({})",
+                        combination.describe(&sweep.env, &sweep.settings)
+                    )
+                } else {
+                    label.clone()
+                };
+                if !config.dry_run {
+                    let reasons = config.policy.failures(&config, &results)?;
+                    failures.extend(reasons.into_iter().map(|r| format!("{r}{label}")));
+                }
+                lineup.push((label, results));
+            }
+            if games.len() > 1 {
+                writeln!(human_output(config.summary_only))?;
+            }
+        }
+        if let Some(champion) = champion.filter(|_| !config.dry_run) {
+            compare_champion(&config, &lineup, (&games, &champion), promote)?;
+        }
+        Ok((lineup, failures))
+    } else {
+        if champion.is_some() {
+            return Err(AppError::WatchChampion.into());
+        }
+        if games.len() > 1 {
+            return Err(AppError::WatchSeveralGames.into());
+        }
+        sweep.apply_first(&mut config);
+        watch::run(&config, &watched)?;
+        Ok((Vec::new(), Vec::new()))
+    }
+}
+
+/// Compares the results of every game but the champion, the last one, with its results over the
+/// same combination of the sweep, offering to promote the game if there's a single one and it
+/// beats the champion everywhere
+fn compare_champion(
+    config: &TestConfig,
+    lineup: &[(String, TestResults)],
+    (games, champion): (&[PathBuf], &Path),
+    promote: bool,
+) -> Result<()> {
+    let combinations = lineup.len() / games.len();
+    let (current, champion_results) = lineup.split_at(lineup.len() - combinations);
+    let seat = config.focus.unwrap_or(0);
+    let player = config.players[seat].as_string();
+    let mut out = human_output(config.summary_only);
+    let mut beaten = true;
+    for (i, (label, results)) in current.iter().enumerate() {
+        let comparison = Comparison::new(results, &champion_results[i % combinations].1, seat);
+        if !label.is_empty() {
+            writeln!(out, "{}:", label.trim_start())?;
+        }
+        comparison.write(&mut out, &player, champion)?;
+        beaten &= comparison.beats();
+    }
+    writeln!(out)?;
+    if beaten && games.len() == 2 {
+        champion::offer_promotion(&mut out, &games[0], champion, promote)?;
+    }
+    Ok(())
+}
+
+/// Runs the tests on the workers that connect to the coordinator
+fn serve_jobs(mut args: ServeArgs) -> Result<()> {
+    let sweep = Sweep::take(&mut args.run);
+    let mut config = test_config(args.run)?;
+    if config.fail_fast {
+        return Err(AppError::FailFastServeJobs.into());
+    }
+    if config.load_target.is_some() {
+        return Err(AppError::LoadTargetRemote.into());
+    }
+    config.serve_jobs = Some(ServeJobs {
+        listen: args.listen,
+        batch: args.batch,
+    });
+    run_sweep(&config, &sweep).map(drop)
+}
+
+/// Serves the web UI, running the tests in the meantime if asked to
+fn serve_web(args: ServeWebArgs) -> Result<()> {
+    let Some(ServeRun::Run(mut run)) = args.run else {
+        return webui::serve(&args.listen, args.runs_dir, None, args.queue)?
+            .join()
+            .map_err(|_| AppError::WebUiStopped.into());
+    };
+
+    let sweep = Sweep::take(&mut run);
+    let mut config = test_config(run)?;
+    if config.dry_run {
+        return run_sweep(&config, &sweep).map(drop);
+    }
+    let live = Arc::new(Metrics::new());
+    config.live = Some(Arc::clone(&live));
+    let runs_dir = config.artifacts.clone().unwrap_or(args.runs_dir);
+    let server = webui::serve(&args.listen, runs_dir, Some(live), args.queue)?;
+    run_sweep(&config, &sweep)?;
+
+    println!("Still serving the web UI, press Ctrl-C to stop");
+    server.join().map_err(|_| AppError::WebUiStopped.into())
+}
+
+/// Compares two saved runs, saving the replays of the seeds where the player placed worse if
+/// asked to
+fn compare(args: &CompareArgs) -> Result<()> {
+    let seeds = history::compare(
+        &args.baseline,
+        &args.candidate,
+        args.focus.as_deref(),
+        args.drops,
+    )?;
+    let Some(dir) = &args.save_drops else {
+        return Ok(());
+    };
+
+    let invalid = || AppError::InvalidRunFile(args.candidate.join("config.txt"));
+    let value = |key| history::config_value(&args.candidate, key).ok_or_else(invalid);
+    let players: Vec<PlayerName> = value("players")?
+        .trim_matches(['[', ']'])
+        .split(", ")
+        .map(|p| PlayerName::try_from(p.trim_matches('"')))
+        .collect::<Result<_, _>>()
+        .map_err(|()| invalid())?;
+    let mut config = TestConfig::new(
+        players.try_into().map_err(|_| invalid())?,
+        args.candidate
+            .join("settings.cnf")
+            .to_string_lossy()
+            .into_owned(),
+        PathBuf::from(value("game")?),
+    );
+    config.settings_via = <SettingsVia as clap::ValueEnum>::from_str(&value("settings_via")?, true)
+        .map_err(|_| invalid())?;
+    let runner = GameRunner::new(&config, None, None)?;
+    save_drops(&mut io::stdout(), &runner, &seeds, dir)
+}
+
+/// Saves the replays of the seeds where the focal player placed worse than before
+fn save_drops(out: &mut impl Write, runner: &GameRunner, seeds: &[u32], dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    for &seed in seeds {
+        save_game_output(runner, seed, &dir.join(format!("{seed}.res")))?;
+    }
+    writeln!(out, "Replays of these seeds saved in {}", dir.display())?;
+    Ok(())
+}
+
+fn queue(args: QueueArgs) -> Result<()> {
+    let queue = Queue::new(&args.runs_dir);
+    match args.action {
+        QueueAction::Add { args } => {
+            let id = queue.add(&env::current_dir()?, &args)?;
+            println!("Queued {id}");
+        }
+        QueueAction::List => {
+            for request in queue.list() {
+                println!(
+                    "{} {:<8} {}",
+                    request.id,
+                    request.state.name(),
+                    request.args.join(" ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tournament(args: TournamentArgs) -> Result<()> {
+    Tournament {
+        players: args.players,
         seed: args.seed,
         instances: args.instances,
-        players: [
-            args.player1.as_str().try_into().unwrap(),
-            args.player2.as_str().try_into().unwrap(),
-            args.player3.as_str().try_into().unwrap(),
-            args.player4.as_str().try_into().unwrap(),
-        ],
+        swiss: args.swiss,
         settings_file: args.game_settings,
-    };
+        dry_run: args.dry_run,
+        seed_plan: args.seed_plan,
+        focus: args.focus,
+        classification: args.classification,
+        placement_points: args
+            .placement_points
+            .try_into()
+            .map_err(|points: Vec<u32>| AppError::InvalidPlacementPoints(points.len()))?,
+        game: path::absolute(&args.game)?,
+    }
+    .run()
+}
 
-    run_tests(config)?;
+fn gauntlet(args: GauntletArgs) -> Result<()> {
+    Gauntlet {
+        player: args.player,
+        pool: args.pool,
+        mixes: args.mixes,
+        seed: args.seed,
+        instances: args.instances,
+        settings_file: args.game_settings,
+        dry_run: args.dry_run,
+        seed_plan: args.seed_plan,
+        game: path::absolute(&args.game)?,
+    }
+    .run()
+}
 
-    Ok(())
+fn league(args: LeagueArgs) -> Result<()> {
+    let mut league = League::load(&args.file)?;
+    let mut failures = Vec::new();
+    if let LeagueAction::Play(run) = args.action {
+        let players = [&run.player1, &run.player2, &run.player3, &run.player4].map(Clone::clone);
+        let dry_run = run.dry_run;
+        let lineup;
+        (lineup, failures) = run_lineup(*run)?;
+        for (_, results) in lineup {
+            league.record(&players, &results);
+        }
+        if dry_run {
+            return Ok(());
+        }
+        league.save()?;
+        println!();
+    }
+    league.print();
+
+    // The games still count for the league
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::RunFailed(failures.join("; ")).into())
+    }
+}
+
+fn parse_players(names: [String; 4]) -> [PlayerName; 4] {
+    names.map(|name| name.as_str().try_into().unwrap())
+}
+
+/// Builds the --player-src players into every game, before anything tries to run them. Returns
+/// the absolute paths of the games
+fn install_players(args: &Args) -> Result<Vec<PathBuf>> {
+    let games = args
+        .game
+        .iter()
+        .map(path::absolute)
+        .collect::<Result<Vec<_>, _>>()?;
+    for game in &games {
+        if args.dry_run {
+            for source in &args.player_src {
+                println!(
+                    "Would install {} and rebuild {}",
+                    source.display(),
+                    game.display()
+                );
+            }
+        } else {
+            build::install_players(&args.player_src, game)?;
+        }
+    }
+    Ok(games)
+}
+
+/// Checks the CPU, disk and sandbox options can work, before playing anything
+fn check_resources(args: &Args) -> Result<(), AppError> {
+    match args.load_target {
+        Some(target) if target.is_nan() || target <= 0. => {
+            return Err(AppError::InvalidLoadTarget(target));
+        }
+        Some(_) if !args.workers.is_empty() => return Err(AppError::LoadTargetRemote),
+        _ => {}
+    }
+    for (flag, value) in [
+        ("--cpu-budget", args.cpu_budget),
+        ("--judge-slowdown", Some(args.judge_slowdown)),
+    ] {
+        if let Some(value) = value.filter(|v| v.is_nan() || *v <= 0. || v.is_infinite()) {
+            return Err(AppError::InvalidCpuBudget(flag, value));
+        }
+    }
+    if args.enforce_cpu_budget && !args.workers.is_empty() {
+        return Err(AppError::CpuBudgetRemote);
+    }
+    if args.sandbox.is_some() && !args.workers.is_empty() {
+        return Err(AppError::SandboxRemote);
+    }
+    if args.isolate && !args.workers.is_empty() {
+        return Err(AppError::IsolateRemote);
+    }
+    match args.compress {
+        Some(compression) if !args.dry_run => compression.check(),
+        _ => Ok(()),
+    }
 }
 
-enum ExecutionResults {
-    Ok { points: [u32; 4] },
-    Crash { seed: u32 },
+/// Seat of the --focus player
+fn focus_seat(players: &[PlayerName; 4], focus: Option<String>) -> Result<Option<usize>> {
+    let seat = focus
+        .map(|name| {
+            players
+                .iter()
+                .position(|p| p.as_string() == name)
+                .ok_or(AppError::UnknownPlayer(name))
+        })
+        .transpose()?;
+    Ok(seat)
 }
 
-impl Default for ExecutionResults {
-    fn default() -> Self {
-        Self::Ok { points: [0; 4] }
+fn policy(args: &Args) -> Result<Policy> {
+    let policy = Policy {
+        fail_on: args.fail_on,
+        baseline: args.baseline.clone(),
+        max_regression: args.max_regression,
+        min_win_rate: args.min_win_rate,
+        min_score: args.min_score,
+    };
+    policy.validate()?;
+    Ok(policy)
+}
+
+fn test_config(args: Args) -> Result<TestConfig> {
+    // Presets get expanded by `run`, before getting here
+    if !args.preset.is_empty() {
+        return Err(AppError::PresetOutsideRun.into());
     }
+
+    // The other games are run by `run_lineup`, the rest of the commands only use the first one
+    let game = install_players(&args)?
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| runner::DEFAULT_GAME.into());
+
+    check_resources(&args)?;
+    let policy = policy(&args)?;
+    let players = parse_players([args.player1, args.player2, args.player3, args.player4]);
+    let sort_by = SortBy::parse(
+        &args.sort_by,
+        parser::MetricParser::new(&args.metrics)?.names(),
+        args.normalize.is_some(),
+    )?;
+    let focus = focus_seat(&players, args.focus)?;
+
+    Ok(TestConfig {
+        seed: args.seed,
+        instances: args.instances,
+        repeat: args.repeat,
+        players,
+        settings_file: args.game_settings,
+        game,
+        game_timeout: args.game_timeout.map(|t| Duration::from_secs(t.get())),
+        cpu_budget: args.cpu_budget.map(|budget| CpuBudget {
+            budget: Duration::from_secs_f64(budget),
+            slowdown: args.judge_slowdown,
+            enforce: args.enforce_cpu_budget,
+        }),
+        stderr_limit: args.stderr_limit,
+        settings_via: args.settings_via,
+        spawn_retries: args.spawn_retries,
+        workers: args.workers,
+        serve_jobs: None,
+        sandbox: args.sandbox,
+        isolate: args.isolate,
+        env: Vec::new(),
+        settings: Vec::new(),
+        quiet: false,
+        revision: Revision::current(),
+        score_regex: args.score_regex,
+        timeout_regex: args.timeout_regex,
+        disqualified_regex: args.disqualified_regex,
+        warning_regex: args.warning_regex,
+        timeline: args
+            .timeline
+            .then_some((args.round_regex, args.round_score_regex)),
+        metrics: args.metrics,
+        save_output: args.save_output,
+        artifacts: args.artifacts,
+        retention: Retention {
+            retain: args.retain,
+            count: args.retain_count,
+            max_bytes: args
+                .max_artifacts_size
+                .map(|mib| mib.saturating_mul(1 << 20)),
+            compress: args.compress,
+        },
+        crash_report: args.crash_report,
+        seed_plan: args.seed_plan,
+        stream: args.stream,
+        progress_json: args.progress_json,
+        pre_game: args.pre_game,
+        post_game: args.post_game,
+        metrics_addr: args.metrics_addr,
+        live: None,
+        dashboard: args.dashboard,
+        plain: args.plain || !io::stderr().is_terminal(),
+        summary_only: args.summary_only,
+        summary_every: SummaryEvery {
+            games: args.summary_every,
+            interval: args.summary_interval.map(|t| Duration::from_secs(t.get())),
+        },
+        stats_file: args.stats_file,
+        open_viewer: args.open_viewer,
+        viewer: args.viewer,
+        save_interesting: args.save_interesting,
+        notify: args.notify,
+        webhook: args.webhook.map(|url| (url, args.webhook_format)),
+        focus,
+        focus_count: args.focus_count,
+        drops: args.drops,
+        save_drops: args.save_drops,
+        junit: args.junit.map(|path| (path, args.junit_fail_on)),
+        policy,
+        fail_fast: args.fail_fast,
+        load_target: args.load_target,
+        mirror: args.mirror.is_some(),
+        teams: args.teams,
+        sort_by,
+        top: args.top,
+        normalize: args.normalize,
+        dry_run: args.dry_run,
+    })
 }
 
-#[derive(Default)]
-struct PlayerResults {
-    total_points: u32,
-    total_wins: u32,
+/// Environment variables and settings overrides, possibly swept over several values
+struct Sweep {
+    env: Vec<Assignment>,
+    settings: Vec<Assignment>,
 }
 
-#[derive(Default)]
-struct TestResults {
-    player_results: [PlayerResults; 4],
-    failed_seeds: Vec<u32>,
+impl Sweep {
+    fn take(args: &mut Args) -> Self {
+        Self {
+            env: mem::take(&mut args.env),
+            settings: mem::take(&mut args.settings),
+        }
+    }
+
+    /// Only keeps the first value of every assignment, for modes that don't sweep
+    fn apply_first(&self, config: &mut TestConfig) {
+        let combination = sweep::combinations(&self.env, &self.settings).remove(0);
+        config.env = combination.env;
+        config.settings = combination.settings;
+    }
+}
+
+/// Runs the tests once for every combination of the swept values
+fn run_sweep(config: &TestConfig, sweep: &Sweep) -> Result<Vec<(Combination, TestResults)>> {
+    let mut config = config.clone();
+    let is_sweep = sweep::is_sweep(&sweep.env, &sweep.settings);
+    let combinations = sweep::combinations(&sweep.env, &sweep.settings);
+    let total = combinations.len();
+
+    let mut out = human_output(config.summary_only);
+    let mut ret = Vec::new();
+    let mut seed_plan = SeedPlan::new("configurations", Layout::Shared);
+    for (i, combination) in combinations.into_iter().enumerate() {
+        seed_plan.record(
+            combination.describe(&sweep.env, &sweep.settings),
+            config.seed,
+            config.instances.get(),
+        );
+        if is_sweep {
+            writeln!(
+                out,
+                "Configuration {}/{total}: {}",
+                i + 1,
+                combination.describe(&sweep.env, &sweep.settings)
+            )?;
+        }
+        config.env.clone_from(&combination.env);
+        config.settings.clone_from(&combination.settings);
+        ret.push((combination, run_tests(&config)?));
+    }
+    if is_sweep || config.seed_plan.is_some() {
+        seed_plan.report(&mut out, config.seed_plan.as_deref())?;
+    }
+    Ok(ret)
 }
 
-fn run_tests(config: TestConfig) -> Result<()> {
+fn run_tests(config: &TestConfig) -> Result<TestResults> {
+    if config.dry_run {
+        plan::print(config, rayon::current_num_threads())?;
+        return Ok(TestResults::default());
+    }
     let min_seed = config.seed;
 
-    let max_seed = config
+    config
         .seed
         .checked_add(config.instances.get() - 1)
         .ok_or(AppError::SeedRangeOutOfBounds)?;
+    let repeat = config.repeat.get();
+    let total_games = config
+        .instances
+        .get()
+        .checked_mul(repeat)
+        .ok_or(AppError::TooManyGames)?;
 
-    let re = Regex::new(r"player \S* got score (\d*)")?;
+    let artifacts = config
+        .artifacts
+        .as_deref()
+        .map(RunArtifacts::create)
+        .transpose()?;
+    let save_output = config
+        .save_output
+        .clone()
+        .or_else(|| artifacts.as_ref().map(RunArtifacts::replays_dir));
+    let crash_dumps = artifacts.as_ref().map(RunArtifacts::crashes_dir);
 
-    let mut f = File::open(config.settings_file)?;
-    let mut settings = String::new();
-    f.read_to_string(&mut settings)?;
+    let runner = GameRunner::new(config, save_output, crash_dumps)?;
+    tracing::info!(
+        first_seed = min_seed,
+        games = total_games,
+        players = ?runner.player_names(),
+        "Starting run"
+    );
+    let (observers, pb, dashboard) = progress_observers(config, &runner, total_games)?;
+    pb.tick();
 
-    let pb = ProgressBar::new(config.instances.get().into()).with_style(ProgressStyle::with_template(
-        " Running games... ({pos}/{len}) {wide_bar} {percent}% ",
-    )?);
+    let results = if let Some(serve) = &config.serve_jobs {
+        let seeds = (0..total_games).map(|i| min_seed + i / repeat).collect();
+        jobs::serve(serve, &runner, seeds, repeat, &observers, &pb)?
+    } else {
+        let throttle = config.load_target.map(Throttle::new);
+        play_local(
+            &runner,
+            &observers,
+            &pb,
+            min_seed,
+            total_games,
+            repeat,
+            throttle.as_ref(),
+        )?
+    };
 
-    pb.tick();
+    for observer in &observers {
+        observer.run_finished()?;
+    }
 
-    let results = (min_seed..=max_seed)
-        .into_par_iter()
-        .map::<_, Result<_>>(|seed| {
-            let mut child = Command::new("./Game")
-                .args(config.players.map(|p| p.as_string()))
-                .arg("-s")
-                .arg(seed.to_string())
-                .stdin(Stdio::piped())
-                .stdout(Stdio::null())
-                .stderr(Stdio::piped())
-                .spawn()?;
-
-            let mut stdin = child
-                .stdin
-                .take()
-                .ok_or(AppError::BrokenChildCommunication)?;
-            stdin.write_all(settings.as_bytes())?;
-
-            let mut stderr = child
-                .stderr
-                .take()
-                .ok_or(AppError::BrokenChildCommunication)?;
-            let mut output = String::new();
-            stderr.read_to_string(&mut output)?;
-
-            if !child.wait()?.success() {
-                return Ok(ExecutionResults::Crash { seed });
-            }
-
-            let mut ret = [0u32; 4];
-
-            for (i, points) in re
-                .captures_iter(&output)
-                .map(|caps| caps.get(1).unwrap().as_str().parse().unwrap())
-                .enumerate()
-            {
-                ret[i] = points;
-            }
-
-            Ok(ExecutionResults::Ok { points: ret })
-        })
-        .progress_with(pb)
-        .map::<_, Result<_>>(|x| {
-            let mut ret = TestResults::default();
-            match x? {
-                ExecutionResults::Ok { points } => {
-                    for i in 0..4 {
-                        ret.player_results[i].total_points = points[i];
-                        if points[i] == *points.iter().max().unwrap() {
-                            ret.player_results[i].total_wins = 1;
-                        }
-                    }
-                }
-                ExecutionResults::Crash { seed } => ret.failed_seeds = vec![seed],
+    if let Some(dashboard) = &dashboard {
+        dashboard.finish();
+    }
+
+    if config.summary_only && !config.quiet {
+        report::write_summary_lines(&mut io::stdout(), config, &results, runner.metric_names())?;
+    } else if !config.quiet {
+        let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+        report::write_results(
+            &mut io::stdout(),
+            config,
+            &results,
+            runner.metric_names(),
+            color,
+        )?;
+    }
+
+    finish_run(config, &runner, &results, artifacts)?;
+    if !config.quiet && !config.summary_only && github::is_enabled() {
+        github::annotate(&results);
+        github::write_summary(config, &results, runner.metric_names())?;
+    }
+    if config.notify {
+        notify::send(
+            "EDA game tester: run finished",
+            &notify::headline(config, &results),
+        );
+    }
+    if let Some((url, format)) = &config.webhook {
+        // The results are already reported and saved, so this doesn't fail the run
+        if let Err(err) = webhook::post(url, &webhook::payload(config, &results, *format)) {
+            tracing::warn!(%err, "Couldn't post the summary");
+        }
+    }
+    Ok(results)
+}
+
+type Observers = Vec<Box<dyn GameObserver>>;
+
+/// Seeds a game can be ahead of the first unfinished one, so the results waiting for it to be
+/// merged in order stay bounded
+const MAX_AHEAD: u32 = 1024;
+
+/// Plays the games on every thread of the pool, handing out the seeds in order
+fn play_local(
+    runner: &GameRunner,
+    observers: &Observers,
+    pb: &ProgressBar,
+    min_seed: u32,
+    total_games: u32,
+    repeat: u32,
+    throttle: Option<&Throttle>,
+) -> Result<TestResults> {
+    let ordered = Mutex::new(OrderedResults::new(min_seed, repeat));
+    let merged = Condvar::new();
+    let next_game = AtomicU32::new(0);
+    let failed = AtomicBool::new(false);
+    let lock = || ordered.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let play = || -> Result<()> {
+        loop {
+            let i = next_game.fetch_add(1, atomic::Ordering::Relaxed);
+            if i >= total_games {
+                return Ok(());
             }
-            Ok(ret)
-        })
-        .reduce(
-            || Ok(TestResults::default()),
-            |a, b| {
-                let mut a = a?;
-                let b = b?;
-
-                a.failed_seeds.extend_from_slice(&b.failed_seeds);
-                for i in 0..4 {
-                    a.player_results[i].total_points += b.player_results[i].total_points;
-                    a.player_results[i].total_wins += b.player_results[i].total_wins;
+            let seed = min_seed + i / repeat;
+            let mut state = lock();
+            while seed - state.next() >= MAX_AHEAD {
+                if failed.load(atomic::Ordering::Relaxed) {
+                    return Ok(());
                 }
+                state = merged.wait(state).unwrap_or_else(PoisonError::into_inner);
+            }
+            drop(state);
+            let slot = throttle.map(Throttle::acquire);
+            if failed.load(atomic::Ordering::Relaxed) {
+                return Ok(());
+            }
 
-                Ok(a)
-            },
-        )?;
+            for observer in observers {
+                observer.game_started(seed)?;
+            }
+            let result = runner.run(seed)?;
+            if let Some(throttle) = throttle {
+                throttle.record(&result.usage());
+            }
+            drop(slot);
+            if runner.is_cancelled() {
+                // Killed because another game failed, so its result means nothing
+                return Ok(());
+            }
+            if let Some(reason) = runner.failure(&result).filter(|_| runner.fail_fast()) {
+                runner.cancel();
+                return Err(AppError::FailFast(seed, reason).into());
+            }
+            for observer in observers {
+                observer.game_finished(&result)?;
+            }
+            lock().push(seed, result.into());
+            merged.notify_all();
+            pb.inc(1);
+        }
+    };
+    let outcomes = rayon::broadcast(|_| {
+        let outcome = play();
+        if outcome.is_err() {
+            // The seed that failed will never be merged, so nobody should wait for it
+            failed.store(true, atomic::Ordering::Relaxed);
+            next_game.store(total_games, atomic::Ordering::Relaxed);
+            // Taking the lock makes sure the waiting threads see the failure
+            drop(lock());
+            merged.notify_all();
+        }
+        outcome
+    });
+    outcomes.into_iter().collect::<Result<()>>()?;
+    pb.finish();
 
-    println!("Game results:");
-    #[allow(clippy::cast_possible_truncation)] // Correctness: We can't run more than u32::MAX seeds
-    let ok_games = config.instances.get() - results.failed_seeds.len() as u32;
+    Ok(ordered
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner)
+        .finish())
+}
 
-    for (i, res) in results.player_results.iter().enumerate() {
-        println!(
-            "=> Player {} got {} points in average ({}% WR)",
-            config.players[i].as_string(),
-            f64::from(res.total_points) / f64::from(ok_games),
-            f64::from(res.total_wins) * 100. / f64::from(ok_games),
+/// Sets up everything that follows the run as games finish, along with the progress bar
+fn progress_observers(
+    config: &TestConfig,
+    runner: &GameRunner,
+    total_games: u32,
+) -> Result<(Observers, ProgressBar, Option<Arc<Dashboard>>)> {
+    let mut observers: Vec<Box<dyn GameObserver>> = Vec::new();
+    if let Some(path) = &config.stream {
+        observers.push(Box::new(ResultStream::open(path, runner.player_names())?));
+    }
+    if let Some(path) = &config.progress_json {
+        observers.push(Box::new(ProgressEvents::open(path, total_games)?));
+    }
+    if config.pre_game.is_some() || config.post_game.is_some() {
+        observers.push(Box::new(Hooks::new(
+            config.pre_game.clone(),
+            config.post_game.clone(),
+            runner.player_names(),
+            runner.output_dir(),
+            runner.crash_dump_dir(),
+        )));
+    }
+    if let Some(addr) = &config.metrics_addr {
+        let metrics = Metrics::serve(addr)?;
+        metrics.start_run(runner.player_names(), total_games);
+        observers.push(Box::new(metrics));
+    }
+    if let Some(live) = &config.live {
+        live.start_run(runner.player_names(), total_games);
+        observers.push(Box::new(Arc::clone(live)));
+    }
+
+    let dashboard = (config.dashboard && !config.plain && !config.summary_only)
+        .then(|| Dashboard::new(total_games, runner.player_names()))
+        .transpose()?
+        .map(Arc::new);
+    let pb = if config.summary_only {
+        ProgressBar::hidden()
+    } else if config.plain {
+        observers.push(Box::new(PlainProgress::new(total_games)));
+        ProgressBar::hidden()
+    } else if let Some(dashboard) = &dashboard {
+        observers.push(Box::new(Arc::clone(dashboard)));
+        dashboard.progress_bar()
+    } else {
+        ProgressBar::new(total_games.into()).with_style(ProgressStyle::with_template(
+            " Running games... ({pos}/{len}) {wide_bar} {percent}% ",
+        )?)
+    };
+    let summary = Arc::new(InterimSummary::new(
+        runner.player_names(),
+        total_games,
+        config.summary_every,
+        config.stats_file.clone(),
+        pb.clone(),
+    ));
+    summary.watch_signal();
+    observers.push(Box::new(summary));
+
+    Ok((observers, pb, dashboard))
+}
+
+/// Reports, viewer and artifacts produced once every game has finished
+fn finish_run(
+    config: &TestConfig,
+    runner: &GameRunner,
+    results: &TestResults,
+    artifacts: Option<RunArtifacts>,
+) -> Result<()> {
+    let mut out = human_output(config.summary_only);
+    if let Some(seed) = config.open_viewer.and_then(|t| t.resolve(results)) {
+        open_in_viewer(&mut out, config, runner, seed)?;
+    }
+
+    if let Some(baseline) = config.policy.baseline.as_ref().filter(|_| !config.quiet) {
+        let before = history::saved_scores(baseline)?;
+        report_drops(&mut out, config, runner, &before, results)?;
+    }
+
+    if config.save_interesting {
+        let dir = artifacts.as_ref().map_or_else(
+            || PathBuf::from("interesting"),
+            RunArtifacts::interesting_dir,
         );
+        fs::create_dir_all(&dir)?;
+
+        writeln!(out, "Notable games:")?;
+        for game in interesting::find(results, config.focus.unwrap_or(0)) {
+            let path = dir.join(format!("{}-{}.res", game.label, game.seed));
+            save_game_output(runner, game.seed, &path)?;
+            writeln!(
+                out,
+                "=> {} (seed {}): {}",
+                game.label,
+                game.seed,
+                path.display()
+            )?;
+        }
+        writeln!(out)?;
     }
-    println!();
 
-    if !results.failed_seeds.is_empty() {
-        println!("Some games crashed! Faulty seeds:");
-        for seed in results.failed_seeds {
-            println!("=> {seed}");
+    if let Some((path, fail_on)) = &config.junit {
+        fs::write(path, junit::report(config, results, fail_on))?;
+    }
+
+    let (replays, crash_dumps) = (runner.output_dir(), runner.crash_dump_dir());
+    if config.retention.is_enabled() && (replays.is_some() || crash_dumps.is_some()) {
+        config.retention.apply(
+            &mut out,
+            replays,
+            crash_dumps,
+            results,
+            config.focus.unwrap_or(0),
+        )?;
+    }
+    let crash_report = crash_report::has_failures(config, results)
+        .then(|| crash_report::report(config, results, replays, crash_dumps).to_string() + "\n");
+    if let Some((path, report)) = config.crash_report.as_ref().zip(crash_report.as_ref()) {
+        fs::write(path, report)?;
+    }
+
+    if let Some(mut artifacts) = artifacts {
+        let mut summary = Vec::new();
+        report::write_results(&mut summary, config, results, runner.metric_names(), false)?;
+
+        artifacts.write(
+            "config.txt",
+            "Resolved test configuration",
+            config.describe(),
+        )?;
+        artifacts.write("settings.cnf", "Game settings used", runner.settings())?;
+        artifacts.write("summary.txt", "Final summary", summary)?;
+        artifacts.write(
+            "seeds.csv",
+            "Per-seed results",
+            results.seeds_csv(runner.player_names()),
+        )?;
+
+        if let Some(report) = crash_report {
+            artifacts.write(
+                crash_report::FILE_NAME,
+                "Failures of the run, grouped by kind",
+                report,
+            )?;
+        }
+
+        let dir = artifacts.finish(config.revision.as_ref(), metadata::collect(config))?;
+        writeln!(out, "Run artifacts saved in {}", dir.display())?;
+    }
+
+    Ok(())
+}
+
+/// Lists the seeds where the focal player placed worse than in `before`, saving their replays
+/// with --save-drops
+fn report_drops(
+    out: &mut impl Write,
+    config: &TestConfig,
+    runner: &GameRunner,
+    before: &regressions::Scores,
+    results: &TestResults,
+) -> Result<()> {
+    let seat = config.focus.unwrap_or(0);
+    let drops = regressions::find(before, &regressions::scores(results), seat);
+    regressions::write(out, &drops, &config.players[seat].as_string(), config.drops)?;
+    if let Some(dir) = &config.save_drops {
+        let seeds: Vec<u32> = drops.iter().take(config.drops).map(|d| d.seed).collect();
+        save_drops(out, runner, &seeds, dir)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Writes the output of a game to `path`, copying it if it was already saved during the run or
+/// running the game again otherwise
+fn save_game_output(runner: &GameRunner, seed: u32, path: &Path) -> Result<()> {
+    let saved = runner
+        .output_dir()
+        .map(|dir| dir.join(format!("{seed}.res")))
+        .filter(|saved| saved.exists());
+
+    match saved {
+        Some(saved) if saved == path => {}
+        Some(saved) => {
+            fs::copy(saved, path)?;
+        }
+        None => {
+            runner.run_saving(seed, Some(path))?;
         }
     }
+    Ok(())
+}
+
+/// Opens a game in the viewer, running it again if its output wasn't saved
+fn open_in_viewer(
+    out: &mut impl Write,
+    config: &TestConfig,
+    runner: &GameRunner,
+    seed: u32,
+) -> Result<()> {
+    let output = runner
+        .output_dir()
+        .map_or_else(PathBuf::new, Path::to_path_buf)
+        .join(format!("{seed}.res"));
+    save_game_output(runner, seed, &output)?;
 
+    writeln!(
+        out,
+        "Opening seed {seed} in the viewer ({})",
+        output.display()
+    )?;
+    viewer::open(&config.viewer, &output)?;
     Ok(())
 }
@@ -0,0 +1,45 @@
+use crate::results::TestResults;
+
+/// Points a player won its games by, over the runner-up
+pub struct MarginStats {
+    pub wins: u32,
+    pub average: f64,
+    pub min: u32,
+    pub p25: u32,
+    pub median: u32,
+    pub p75: u32,
+    pub max: u32,
+}
+
+impl MarginStats {
+    /// Margins of the games won by the player in `seats`, or `None` if it won none. Ties count as
+    /// wins by 0 points, and the other seats of the same player aren't runners-up
+    pub fn compute(results: &TestResults, seats: &[usize]) -> Option<Self> {
+        let mut margins: Vec<u32> = results
+            .games
+            .iter()
+            .filter_map(|g| g.points)
+            .filter_map(|points| {
+                let own = seats.iter().map(|&s| points[s]).max()?;
+                let others = (0..4)
+                    .filter(|s| !seats.contains(s))
+                    .map(|s| points[s])
+                    .max()?;
+                own.checked_sub(others)
+            })
+            .collect();
+        margins.sort_unstable();
+
+        let wins = u32::try_from(margins.len()).ok().filter(|&w| w > 0)?;
+        let percentile = |p: usize| margins[(margins.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            wins,
+            average: margins.iter().map(|&m| f64::from(m)).sum::<f64>() / f64::from(wins),
+            min: margins[0],
+            p25: percentile(25),
+            median: percentile(50),
+            p75: percentile(75),
+            max: margins[margins.len() - 1],
+        })
+    }
+}
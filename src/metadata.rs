@@ -0,0 +1,149 @@
+//! What a run was made of, embedded in its exports so they can be told apart and traced back,
+//! and `reproduce`, which plays the same run again from them.
+
+use crate::errors::AppError;
+use crate::json::Json;
+use crate::TestConfig;
+
+use color_eyre::eyre::Result;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+use tracing::warn;
+
+/// FNV-1a, which unlike the hasher of the standard library gives the same hash on every version
+/// and machine
+fn hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("fnv1a64:{hash:016x}")
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|bytes| hash(&bytes))
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut name = [0u8; 256];
+    // SAFETY: gethostname writes at most `name.len()` bytes to the buffer we hand it
+    if unsafe { libc::gethostname(name.as_mut_ptr().cast(), name.len()) } != 0 {
+        return None;
+    }
+    let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    Some(String::from_utf8_lossy(&name[..len]).into_owned())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
+/// The tester and command line a run came from, the configuration they resolved to and hashes
+/// of the settings file and game binary it played with
+pub fn collect(config: &TestConfig) -> Json {
+    let command_line: Vec<String> = env::args_os()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    Json::object([
+        ("tester_version", env!("CARGO_PKG_VERSION").into()),
+        ("command_line", command_line.into()),
+        (
+            "working_dir",
+            env::current_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .into(),
+        ),
+        ("hostname", hostname().into()),
+        ("config", config.describe().into()),
+        ("settings_file", config.settings_file.as_str().into()),
+        (
+            "settings_hash",
+            hash_file(Path::new(&config.settings_file)).into(),
+        ),
+        ("game", config.game.to_string_lossy().into_owned().into()),
+        ("game_hash", hash_file(&config.game).into()),
+    ])
+}
+
+/// The metadata as `name`/`value` pairs, for formats without nesting
+pub fn properties(config: &TestConfig) -> Vec<(String, String)> {
+    let Json::Object(fields) = collect(config) else {
+        return Vec::new();
+    };
+    fields
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Json::String(s) => s,
+                Json::Array(args) => args
+                    .iter()
+                    .filter_map(Json::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                value => value.to_string(),
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// Warns about what changed since the run, which can make it play differently
+fn check_unchanged(metadata: &Json) {
+    let recorded = |key| metadata.get(key).and_then(Json::as_str);
+    if let Some(version) = recorded("tester_version").filter(|&v| v != env!("CARGO_PKG_VERSION")) {
+        warn!(
+            recorded = version,
+            current = env!("CARGO_PKG_VERSION"),
+            "The run was made by another version of the tester"
+        );
+    }
+    for (file, hash_key, what) in [
+        ("settings_file", "settings_hash", "settings file"),
+        ("game", "game_hash", "game binary"),
+    ] {
+        let (Some(path), Some(hash)) = (recorded(file), recorded(hash_key)) else {
+            continue;
+        };
+        let path = Path::new(recorded("working_dir").unwrap_or(".")).join(path);
+        if hash_file(&path).as_deref() != Some(hash) {
+            warn!(path = %path.display(), "The {what} changed since the run");
+        }
+    }
+}
+
+/// Plays a run again with the command line recorded in one of its exports (or in the manifest
+/// of a run directory), from the folder it was run in, exiting as it does
+pub fn reproduce(path: &Path) -> Result<()> {
+    let path = if path.is_dir() {
+        path.join("manifest.json")
+    } else {
+        path.to_owned()
+    };
+    let text = fs::read_to_string(&path).map_err(|_| AppError::MissingFile(path.clone()))?;
+    let metadata = Json::parse(&text)
+        .and_then(|json| json.get("metadata").cloned())
+        .ok_or_else(|| AppError::MissingMetadata(path.clone()))?;
+    let args: Vec<&str> = metadata
+        .get("command_line")
+        .and_then(Json::as_array)
+        .and_then(|args| args.iter().map(Json::as_str).collect())
+        .filter(|args: &Vec<&str>| !args.is_empty())
+        .ok_or_else(|| AppError::MissingMetadata(path.clone()))?;
+    let dir = metadata
+        .get("working_dir")
+        .and_then(Json::as_str)
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+
+    check_unchanged(&metadata);
+    println!("Reproducing in {}: {}", dir.display(), args.join(" "));
+    let status = Command::new(env::current_exe()?)
+        .args(&args[1..])
+        .current_dir(&dir)
+        .status()?;
+    process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,202 @@
+use crate::errors::AppError;
+use crate::http::{self, Response};
+use crate::results::LiveStats;
+use crate::runner::{ExecutionResults, GameObserver};
+
+use color_eyre::eyre::Result;
+use std::{
+    net::TcpListener,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::Instant,
+};
+
+/// The address the metrics are served on, and the statistics served
+static SERVER: OnceLock<(String, Arc<Metrics>)> = OnceLock::new();
+
+struct State {
+    names: [String; 4],
+    total: u32,
+    started: Instant,
+    stats: LiveStats,
+    /// Games finished and crashed over every run, which the counters are
+    finished_total: u64,
+    crashed_total: u64,
+}
+
+/// Statistics of the run at some point
+pub struct Snapshot {
+    pub names: [String; 4],
+    pub total: u32,
+    pub finished: u32,
+    pub crashes: u32,
+    pub finished_total: u64,
+    pub crashed_total: u64,
+    pub per_second: f64,
+    pub win_rates: [f64; 4],
+    pub average_scores: [f64; 4],
+}
+
+/// Live statistics of the current run, which can be served over HTTP in the Prometheus text
+/// format at `/metrics`
+pub struct Metrics {
+    state: Mutex<State>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                names: Default::default(),
+                total: 0,
+                started: Instant::now(),
+                stats: LiveStats::default(),
+                finished_total: 0,
+                crashed_total: 0,
+            }),
+        }
+    }
+
+    /// Starts serving on `addr`, or returns the running server, which outlives every run of a
+    /// sweep. Fails if it's running on another address
+    pub fn serve(addr: &str) -> Result<Arc<Self>> {
+        if let Some((served, metrics)) = SERVER.get() {
+            if served != addr {
+                return Err(AppError::MetricsAddrChanged(served.clone()).into());
+            }
+            return Ok(Arc::clone(metrics));
+        }
+
+        let listener = TcpListener::bind(addr)?;
+        let metrics = Arc::new(Self::new());
+        let served = Arc::clone(&metrics);
+        http::serve(listener, move |request| match request.path.as_str() {
+            "/metrics" => Response::ok("text/plain; version=0.0.4", served.prometheus()),
+            _ => Response::not_found(),
+        });
+        Ok(Arc::clone(
+            &SERVER.get_or_init(|| (addr.to_owned(), metrics)).1,
+        ))
+    }
+
+    /// Resets the statistics of the current run for a new one
+    pub fn start_run(&self, names: &[String; 4], total: u32) {
+        let mut state = self.lock();
+        state.names.clone_from(names);
+        state.total = total;
+        state.started = Instant::now();
+        state.stats = LiveStats::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let run = self.lock();
+        let stats = &run.stats;
+        let finished = stats.games + stats.crashes;
+        let games = f64::from(stats.games.max(1));
+        #[allow(clippy::cast_precision_loss)] // Correctness: Only used for monitoring
+        let average_scores = stats.points.map(|p| p as f64 / games);
+
+        Snapshot {
+            names: run.names.clone(),
+            total: run.total,
+            finished,
+            crashes: stats.crashes,
+            finished_total: run.finished_total,
+            crashed_total: run.crashed_total,
+            per_second: f64::from(finished) / run.started.elapsed().as_secs_f64(),
+            win_rates: stats.wins.map(|w| f64::from(w) / games),
+            average_scores,
+        }
+    }
+
+    /// Statistics in the Prometheus text format
+    pub fn prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut lines = Vec::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>| {
+            lines.push(format!("# HELP eda_{name} {help}"));
+            lines.push(format!("# TYPE eda_{name} {kind}"));
+            for (labels, value) in samples {
+                lines.push(format!("eda_{name}{labels} {value}"));
+            }
+        };
+
+        metric(
+            "run_games",
+            "gauge",
+            "Games in the current run",
+            vec![(String::new(), snapshot.total.into())],
+        );
+        metric(
+            "run_games_finished",
+            "gauge",
+            "Games of the current run finished so far",
+            vec![(String::new(), snapshot.finished.into())],
+        );
+        metric(
+            "run_games_crashed",
+            "gauge",
+            "Games of the current run that crashed so far",
+            vec![(String::new(), snapshot.crashes.into())],
+        );
+        #[allow(clippy::cast_precision_loss)] // Correctness: Only used for monitoring
+        let (finished_total, crashed_total) = (
+            snapshot.finished_total as f64,
+            snapshot.crashed_total as f64,
+        );
+        metric(
+            "games_finished_total",
+            "counter",
+            "Games finished over every run",
+            vec![(String::new(), finished_total)],
+        );
+        metric(
+            "games_crashed_total",
+            "counter",
+            "Games that crashed over every run",
+            vec![(String::new(), crashed_total)],
+        );
+        metric(
+            "games_per_second",
+            "gauge",
+            "Average throughput of the current run",
+            vec![(String::new(), snapshot.per_second)],
+        );
+
+        let label = |i: usize| {
+            let name = snapshot.names[i].replace('\\', r"\\").replace('"', "\\\"");
+            format!("{{player=\"{name}\"}}")
+        };
+        metric(
+            "player_win_rate",
+            "gauge",
+            "Running win rate of every player",
+            (0..4).map(|i| (label(i), snapshot.win_rates[i])).collect(),
+        );
+        metric(
+            "player_average_score",
+            "gauge",
+            "Running average score of every player",
+            (0..4)
+                .map(|i| (label(i), snapshot.average_scores[i]))
+                .collect(),
+        );
+
+        lines.join("\n") + "\n"
+    }
+}
+
+impl GameObserver for Metrics {
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()> {
+        let mut state = self.lock();
+        let (games, crashes) = (state.stats.games, state.stats.crashes);
+        state.stats.record(result);
+        state.finished_total +=
+            u64::from(state.stats.games + state.stats.crashes - games - crashes);
+        state.crashed_total += u64::from(state.stats.crashes - crashes);
+        Ok(())
+    }
+}
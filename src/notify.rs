@@ -0,0 +1,49 @@
+use crate::results::TestResults;
+use crate::TestConfig;
+
+use std::{
+    io::{self, Write},
+    process::Command,
+};
+
+/// One-line summary of the run: the leading player and the number of games
+pub fn headline(config: &TestConfig, results: &TestResults) -> String {
+    let games = results.ok_games();
+    let (seat, leader) = results
+        .player_results
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, r)| (r.total_wins, r.total_points))
+        .unwrap();
+    let mut parts = vec![format!(
+        "{} leads with {:.1}% WR over {games} games",
+        config.players[seat].as_string(),
+        f64::from(leader.total_wins) * 100. / f64::from(games.max(1))
+    )];
+    if !results.failed_seeds.is_empty() {
+        parts.push(format!("{} crashed", results.failed_seeds.len()));
+    }
+    parts.join(", ")
+}
+
+/// Shows a desktop notification, ringing the terminal bell if there's no way to do so
+pub fn send(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {body:?} with title {title:?}"
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args([title, body]);
+        command
+    };
+
+    let shown = command.output().is_ok_and(|o| o.status.success());
+    if !shown {
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\x07");
+        let _ = stderr.flush();
+    }
+}
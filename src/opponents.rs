@@ -0,0 +1,134 @@
+//! How a player did depending on the opponents it shared the table with, over the mixes of a
+//! gauntlet or the tables of a tournament, rather than as one number blending all of them.
+
+use crate::results::TestResults;
+
+use itertools::Itertools;
+
+/// Results of the player over some of its games
+#[derive(Clone, Default)]
+pub struct Record {
+    pub games: u32,
+    pub points: u64,
+    pub wins: u32,
+    pub crashes: u32,
+}
+
+impl Record {
+    /// Results of the player in `seat` over a run
+    pub fn of(results: &TestResults, seat: usize) -> Self {
+        let player_results = &results.player_results[seat];
+        #[allow(clippy::cast_possible_truncation)]
+        // Correctness: We can't run more than u32::MAX seeds
+        let crashes = results.failed_seeds.len() as u32;
+        Self {
+            games: results.ok_games(),
+            points: player_results.total_points.into(),
+            wins: player_results.total_wins,
+            crashes,
+        }
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        self.games += other.games;
+        self.points += other.points;
+        self.wins += other.wins;
+        self.crashes += other.crashes;
+    }
+
+    fn win_rate(&self) -> f64 {
+        f64::from(self.wins) * 100. / f64::from(self.games.max(1))
+    }
+
+    fn average(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)] // Correctness: Only used for display
+        let points = self.points as f64;
+        points / f64::from(self.games.max(1))
+    }
+
+    pub fn line(&self, name: &str) -> String {
+        format!(
+            "{name}: {} games, {:.1} points in average, {:.1}% WR, {} crashed",
+            self.games,
+            self.average(),
+            self.win_rate(),
+            self.crashes
+        )
+    }
+}
+
+/// Results of a player by the exact lineup of its opponents, in the order they were first played
+#[derive(Default)]
+pub struct Breakdown {
+    lineups: Vec<(Vec<String>, Record)>,
+}
+
+impl Breakdown {
+    pub fn add(&mut self, opponents: &[String], record: &Record) {
+        let opponents: Vec<String> = opponents.iter().cloned().sorted().collect();
+        match self.lineups.iter_mut().find(|(o, _)| *o == opponents) {
+            Some((_, total)) => total.add(record),
+            None => self.lineups.push((opponents, record.clone())),
+        }
+    }
+
+    pub fn total(&self) -> Record {
+        let mut total = Record::default();
+        for (_, record) in &self.lineups {
+            total.add(record);
+        }
+        total
+    }
+
+    /// Results with and without every opponent at the table, from the one the player does worst
+    /// against
+    fn by_opponent(&self) -> Vec<(&str, Record, Record)> {
+        self.lineups
+            .iter()
+            .flat_map(|(opponents, _)| opponents)
+            .unique()
+            .map(|opponent| {
+                let mut with = Record::default();
+                let mut without = Record::default();
+                for (opponents, record) in &self.lineups {
+                    if opponents.contains(opponent) {
+                        with.add(record);
+                    } else {
+                        without.add(record);
+                    }
+                }
+                (opponent.as_str(), with, without)
+            })
+            .sorted_by(|(_, a, a_without), (_, b, b_without)| {
+                let delta = |with: &Record, without: &Record| with.win_rate() - without.win_rate();
+                delta(a, a_without).total_cmp(&delta(b, b_without))
+            })
+            .collect()
+    }
+
+    pub fn print(&self, player: &str) {
+        println!("{}", self.total().line(&format!("{player} overall")));
+        println!("Against each mix of opponents:");
+        for (opponents, record) in &self.lineups {
+            println!("=> {}", record.line(&opponents.join(", ")));
+        }
+        println!("Against each opponent, with and without them at the table:");
+        for (opponent, with, without) in self.by_opponent() {
+            let mut parts = vec![format!(
+                "{:.1}% WR and {:.1} points in average over {} games with them",
+                with.win_rate(),
+                with.average(),
+                with.games
+            )];
+            if without.games > 0 {
+                parts.push(format!(
+                    "{:.1}% WR and {:.1} points over {} games without",
+                    without.win_rate(),
+                    without.average(),
+                    without.games
+                ));
+            }
+            println!("=> {opponent}: {}", parts.join(", "));
+        }
+    }
+}
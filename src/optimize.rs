@@ -0,0 +1,258 @@
+//! Iterative parameter optimization with SPSA (simultaneous perturbation stochastic
+//! approximation).
+//!
+//! Every iteration perturbs all the parameters at once in a random direction, plays a batch of
+//! seeds on each side of the current point and moves along the estimated gradient. Both sides
+//! play the same seeds, and every iteration plays new ones.
+
+use crate::errors::AppError;
+use crate::results::TestResults;
+use crate::tune::RankBy;
+use crate::TestConfig;
+
+use color_eyre::eyre::Result;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Initial step size, in the normalized `[0, 1]` range of every parameter
+const STEP_GAIN: f64 = 0.5;
+/// Initial perturbation size, in the normalized range
+const PERTURBATION_GAIN: f64 = 0.1;
+/// Standard SPSA decay exponents for the step and perturbation sizes
+const STEP_DECAY: f64 = 0.602;
+const PERTURBATION_DECAY: f64 = 0.101;
+
+/// Numeric parameter to optimize, given as `KEY=MIN..MAX`
+#[derive(Clone, Debug)]
+pub struct ParamRange {
+    pub key: String,
+    min: f64,
+    max: f64,
+    /// Whether both bounds are integers, in which case so are the proposed values
+    integer: bool,
+}
+
+impl FromStr for ParamRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("expected `KEY=MIN..MAX`, got `{s}`");
+        let (key, range) = s.split_once('=').ok_or_else(err)?;
+        let (min, max) = range.split_once("..").ok_or_else(err)?;
+        let (min, max): (f64, f64) = (
+            min.trim().parse().map_err(|_| err())?,
+            max.trim().parse().map_err(|_| err())?,
+        );
+        if key.is_empty() || min >= max {
+            return Err(err());
+        }
+        Ok(Self {
+            key: key.to_owned(),
+            min,
+            max,
+            integer: range.split("..").all(|b| b.trim().parse::<i64>().is_ok()),
+        })
+    }
+}
+
+impl ParamRange {
+    /// Value of the parameter at the normalized position `x`
+    fn value(&self, x: f64) -> String {
+        let value = self.min + x.clamp(0., 1.) * (self.max - self.min);
+        if self.integer {
+            format!("{}", value.round())
+        } else {
+            format!("{value}")
+        }
+    }
+}
+
+/// What gets optimized and how
+pub struct Optimization {
+    /// Parameters passed as environment variables
+    pub env: Vec<ParamRange>,
+    /// Parameters overriding lines of the settings file
+    pub settings: Vec<ParamRange>,
+    pub iterations: u32,
+    pub objective: RankBy,
+    pub checkpoint: Option<PathBuf>,
+}
+
+/// Progress of the optimization, saved after every iteration so it can be resumed
+struct State {
+    iteration: u32,
+    /// Current point, normalized to `[0, 1]` for every parameter
+    theta: Vec<f64>,
+}
+
+impl State {
+    fn load(path: &Path, dimensions: usize) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)?;
+        let invalid = || AppError::InvalidCheckpoint(path.to_owned());
+
+        let mut iteration = None;
+        let mut theta = None;
+        for line in contents.lines() {
+            match line.split_once(' ') {
+                Some(("iteration", value)) => iteration = value.parse().ok(),
+                Some(("theta", values)) => {
+                    theta = values
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<Vec<f64>, _>>()
+                        .ok();
+                }
+                _ => {}
+            }
+        }
+
+        match (iteration, theta) {
+            (Some(iteration), Some(theta)) if theta.len() == dimensions => {
+                Ok(Some(Self { iteration, theta }))
+            }
+            _ => Err(invalid().into()),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let theta: Vec<_> = self.theta.iter().map(f64::to_string).collect();
+        fs::write(
+            path,
+            format!("iteration {}\ntheta {}\n", self.iteration, theta.join(" ")),
+        )?;
+        Ok(())
+    }
+}
+
+/// Objective of the focal player, in `[0, 1]`: its share of the points scored, or its win rate
+fn objective(results: &TestResults, seat: usize, objective: RankBy) -> f64 {
+    let players = &results.player_results;
+    match objective {
+        RankBy::Score => {
+            let total: u32 = players.iter().map(|r| r.total_points).sum();
+            f64::from(players[seat].total_points) / f64::from(total.max(1))
+        }
+        RankBy::WinRate => {
+            f64::from(players[seat].total_wins) / f64::from(results.ok_games().max(1))
+        }
+    }
+}
+
+impl Optimization {
+    fn params(&self) -> impl Iterator<Item = &ParamRange> {
+        self.env.iter().chain(&self.settings)
+    }
+
+    fn describe(&self, theta: &[f64]) -> String {
+        self.params()
+            .zip(theta)
+            .map(|(p, &x)| format!("{}={}", p.key, p.value(x)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Plays a batch of seeds with the parameters at `theta`
+    fn evaluate(&self, config: &TestConfig, theta: &[f64]) -> Result<TestResults> {
+        let mut config = config.clone();
+        let (env, settings) = theta.split_at(self.env.len());
+        config.env.extend(
+            self.env
+                .iter()
+                .zip(env)
+                .map(|(p, &x)| (p.key.clone(), p.value(x))),
+        );
+        config.settings.extend(
+            self.settings
+                .iter()
+                .zip(settings)
+                .map(|(p, &x)| (p.key.clone(), p.value(x))),
+        );
+        crate::run_tests(&config)
+    }
+
+    pub fn run(&self, config: &TestConfig) -> Result<()> {
+        let dimensions = self.params().count();
+        let seat = config.focus.unwrap_or(0);
+        let mut state = match &self.checkpoint {
+            Some(path) => State::load(path, dimensions)?,
+            None => None,
+        }
+        .unwrap_or(State {
+            iteration: 0,
+            theta: vec![0.5; dimensions],
+        });
+        if state.iteration > 0 {
+            println!(
+                "Resuming from iteration {}: {}",
+                state.iteration,
+                self.describe(&state.theta)
+            );
+        }
+
+        let mut config = config.clone();
+        config.quiet = true;
+        let (first_seed, batch) = (config.seed, config.instances.get());
+        // Leaves room for the SPSA gains to settle before the steps get small
+        let stability = f64::from(self.iterations) / 10.;
+
+        while state.iteration < self.iterations {
+            let k = f64::from(state.iteration);
+            let step = STEP_GAIN / (k + 1. + stability).powf(STEP_DECAY);
+            let perturbation = PERTURBATION_GAIN / (k + 1.).powf(PERTURBATION_DECAY);
+
+            // Seeded by the iteration, so resuming from a checkpoint proposes the same points
+            let mut rng = SmallRng::seed_from_u64(state.iteration.into());
+            let delta: Vec<f64> = (0..dimensions)
+                .map(|_| if rng.gen() { 1. } else { -1. })
+                .collect();
+            let shifted = |sign: f64| -> Vec<f64> {
+                state
+                    .theta
+                    .iter()
+                    .zip(&delta)
+                    .map(|(x, d)| (x + sign * perturbation * d).clamp(0., 1.))
+                    .collect()
+            };
+
+            config.seed = state
+                .iteration
+                .checked_mul(batch)
+                .and_then(|offset| first_seed.checked_add(offset))
+                .ok_or(AppError::SeedRangeOutOfBounds)?;
+            let plus = objective(&self.evaluate(&config, &shifted(1.))?, seat, self.objective);
+            let minus = objective(
+                &self.evaluate(&config, &shifted(-1.))?,
+                seat,
+                self.objective,
+            );
+
+            for (x, d) in state.theta.iter_mut().zip(&delta) {
+                *x = (*x + step * (plus - minus) / (2. * perturbation * d)).clamp(0., 1.);
+            }
+            state.iteration += 1;
+            println!(
+                "Iteration {}/{}: {:.3} / {:.3} => {}",
+                state.iteration,
+                self.iterations,
+                plus,
+                minus,
+                self.describe(&state.theta)
+            );
+
+            if let Some(path) = &self.checkpoint {
+                state.save(path)?;
+            }
+        }
+
+        println!();
+        println!("Optimized configuration: {}", self.describe(&state.theta));
+        Ok(())
+    }
+}
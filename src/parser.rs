@@ -0,0 +1,321 @@
+use crate::errors::AppError;
+use regex::Regex;
+
+/// Default pattern used to find the final score of each player in the game log
+pub const DEFAULT_SCORE_REGEX: &str = r"player (?P<name>\S*) got score (?P<score>\d*)";
+
+/// Extracts the players' scores from the game's stderr.
+///
+/// The score is taken from the `score` named group (or the first group if there's none). If the
+/// pattern has a `name` named group, scores are assigned to the seat with that player name;
+/// otherwise they're assigned in output order.
+pub struct ScoreParser {
+    re: Regex,
+    score_group: usize,
+    name_group: Option<usize>,
+}
+
+impl ScoreParser {
+    pub fn new(pattern: &str) -> Result<Self, AppError> {
+        let re = Regex::new(pattern)?;
+
+        let group_index = |name| re.capture_names().position(|n| n == Some(name));
+
+        let score_group = match group_index("score") {
+            Some(i) => i,
+            None if re.captures_len() > 1 => 1,
+            None => return Err(AppError::MissingScoreGroup),
+        };
+        let name_group = group_index("name");
+
+        Ok(Self {
+            re,
+            score_group,
+            name_group,
+        })
+    }
+
+    /// Returns the scores of each seat, along with how many seats got a score
+    pub fn parse(&self, output: &str, players: &[String; 4]) -> ([u32; 4], usize) {
+        let mut ret = [0u32; 4];
+        let mut assigned = [false; 4];
+        let mut next_seat = 0;
+
+        for caps in self.re.captures_iter(output) {
+            let Some(points) = caps
+                .get(self.score_group)
+                .and_then(|m| m.as_str().parse().ok())
+            else {
+                continue;
+            };
+
+            let seat = match self.name_group.and_then(|i| caps.get(i)) {
+                // Repeated names fill their seats in order
+                Some(name) => (0..4).find(|&i| !assigned[i] && players[i] == name.as_str()),
+                None => (next_seat < 4).then_some(next_seat),
+            };
+
+            if let Some(seat) = seat {
+                ret[seat] = points;
+                assigned[seat] = true;
+                next_seat += 1;
+            }
+        }
+
+        (ret, assigned.iter().filter(|&&a| a).count())
+    }
+}
+
+/// Default pattern for the line the game logs when a player runs out of CPU time
+pub const DEFAULT_TIMEOUT_REGEX: &str = r"player (?P<name>\S*) (?:timed out|ran out of time)";
+
+/// Default pattern for the line the game logs when a player's AI is killed
+pub const DEFAULT_DISQUALIFIED_REGEX: &str =
+    r"player (?P<name>\S*) (?:was killed|got killed|was disqualified)";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlayerStatus {
+    #[default]
+    Alive,
+    TimedOut,
+    Disqualified,
+}
+
+/// Finds the players that timed out or got disqualified during a game.
+///
+/// Both patterns need a `name` named group, used to find the seat of the affected player. Only
+/// the first event of each seat is taken into account.
+pub struct StatusParser {
+    timeout: Regex,
+    disqualified: Regex,
+}
+
+impl StatusParser {
+    pub fn new(timeout_pattern: &str, disqualified_pattern: &str) -> Result<Self, AppError> {
+        let build = |pattern| {
+            let re = Regex::new(pattern)?;
+            if re.capture_names().any(|n| n == Some("name")) {
+                Ok(re)
+            } else {
+                Err(AppError::MissingNameGroup(pattern.to_owned()))
+            }
+        };
+
+        Ok(Self {
+            timeout: build(timeout_pattern)?,
+            disqualified: build(disqualified_pattern)?,
+        })
+    }
+
+    pub fn parse(&self, output: &str, players: &[String; 4]) -> [PlayerStatus; 4] {
+        let mut ret = [PlayerStatus::Alive; 4];
+
+        for line in output.lines() {
+            let (caps, status) = if let Some(caps) = self.timeout.captures(line) {
+                (caps, PlayerStatus::TimedOut)
+            } else if let Some(caps) = self.disqualified.captures(line) {
+                (caps, PlayerStatus::Disqualified)
+            } else {
+                continue;
+            };
+
+            let Some(name) = caps.name("name") else {
+                continue;
+            };
+            if let Some(seat) =
+                (0..4).find(|&i| ret[i] == PlayerStatus::Alive && players[i] == name.as_str())
+            {
+                ret[seat] = status;
+            }
+        }
+
+        ret
+    }
+}
+
+/// Default pattern for the lines the game logs when it rejects something a player did
+pub const DEFAULT_WARNING_REGEX: &str = r"player (?P<name>\S*) (?:issued an? (?:illegal|invalid) \S+|made an? (?:illegal|invalid) move|raised an exception)";
+
+/// Counts the warnings of every player, like illegal commands or exceptions caught by the game.
+///
+/// The pattern needs a `name` named group, and every matching line is a warning. The warnings of
+/// a repeated name go to its first seat, as the game doesn't tell the seats apart.
+pub struct WarningParser {
+    re: Regex,
+}
+
+impl WarningParser {
+    pub fn new(pattern: &str) -> Result<Self, AppError> {
+        let re = Regex::new(pattern)?;
+        if !re.capture_names().any(|n| n == Some("name")) {
+            return Err(AppError::MissingNameGroup(pattern.to_owned()));
+        }
+        Ok(Self { re })
+    }
+
+    pub fn parse(&self, output: &str, players: &[String; 4]) -> [u32; 4] {
+        let mut ret = [0; 4];
+        for caps in output.lines().filter_map(|line| self.re.captures(line)) {
+            let Some(name) = caps.name("name") else {
+                continue;
+            };
+            if let Some(seat) = players.iter().position(|p| p == name.as_str()) {
+                ret[seat] += 1;
+            }
+        }
+        ret
+    }
+}
+
+/// Default pattern for the line marking the start of a new round
+pub const DEFAULT_ROUND_REGEX: &str = r"^round (?P<round>\d+)";
+
+/// Default pattern for the intermediate score of a player during a round
+pub const DEFAULT_ROUND_SCORE_REGEX: &str = r"player (?P<name>\S*) has score (?P<score>\d+)";
+
+/// Number of phases the game timeline is split into (early, mid and late game)
+pub const PHASES: usize = 3;
+
+/// Collects the intermediate scores of each round and summarizes them in game phases.
+///
+/// Every match of the round pattern starts a new round, which inherits the scores of the
+/// previous one until the round score pattern updates them. The scores of a repeated name fill
+/// its seats in order within each round.
+pub struct TimelineParser {
+    round: Regex,
+    score: Regex,
+}
+
+impl TimelineParser {
+    pub fn new(round_pattern: &str, score_pattern: &str) -> Result<Self, AppError> {
+        let round = Regex::new(round_pattern)?;
+        let score = Regex::new(score_pattern)?;
+
+        for group in ["name", "score"] {
+            if !score.capture_names().any(|n| n == Some(group)) {
+                return Err(AppError::MissingTimelineGroups);
+            }
+        }
+
+        Ok(Self { round, score })
+    }
+
+    /// Returns the scores at the end of each game phase, if any round was found
+    pub fn parse(&self, output: &str, players: &[String; 4]) -> Option<[[u32; 4]; PHASES]> {
+        let mut rounds: Vec<[u32; 4]> = Vec::new();
+        let mut assigned = [false; 4];
+
+        for line in output.lines() {
+            if self.round.is_match(line) {
+                rounds.push(rounds.last().copied().unwrap_or_default());
+                assigned = [false; 4];
+            } else if let Some(caps) = self.score.captures(line) {
+                let points = caps.name("score").and_then(|m| m.as_str().parse().ok());
+                let (Some(round), Some(name), Some(points)) =
+                    (rounds.last_mut(), caps.name("name"), points)
+                else {
+                    continue;
+                };
+                if let Some(seat) = next_seat(players, &mut assigned, name.as_str()) {
+                    round[seat] = points;
+                }
+            }
+        }
+
+        if rounds.is_empty() {
+            return None;
+        }
+
+        let mut ret = [[0; 4]; PHASES];
+        for (phase, scores) in ret.iter_mut().enumerate() {
+            *scores = rounds[((phase + 1) * rounds.len()).div_ceil(PHASES) - 1];
+        }
+        Some(ret)
+    }
+}
+
+/// Next seat of `name` that isn't `assigned` yet, starting over from its first seat once all of
+/// them are, so the lines of a repeated name fill its seats in order. `None` if no seat has it
+fn next_seat(players: &[String; 4], assigned: &mut [bool; 4], name: &str) -> Option<usize> {
+    let seats = || (0..4).filter(|&i| players[i] == name);
+    if seats().all(|i| assigned[i]) {
+        seats().for_each(|i| assigned[i] = false);
+    }
+    let seat = seats().find(|&i| !assigned[i])?;
+    assigned[seat] = true;
+    Some(seat)
+}
+
+/// Extracts user-defined per-player metrics from the game output.
+///
+/// Every pattern needs a `name` named group to find the player, and each of its other named
+/// groups is a metric. The matches of a repeated name go to its seats in turns. Numeric captures
+/// are added up over the whole game, while any other capture counts as one occurrence.
+pub struct MetricParser {
+    patterns: Vec<(Regex, Vec<usize>)>,
+    names: Vec<String>,
+}
+
+impl MetricParser {
+    pub fn new(patterns: &[String]) -> Result<Self, AppError> {
+        let mut ret = Self {
+            patterns: Vec::new(),
+            names: Vec::new(),
+        };
+
+        for pattern in patterns {
+            let re = Regex::new(pattern)?;
+            if !re.capture_names().any(|n| n == Some("name")) {
+                return Err(AppError::MissingNameGroup(pattern.clone()));
+            }
+
+            let mut groups = Vec::new();
+            for (i, group) in re.capture_names().enumerate() {
+                match group {
+                    Some("name") | None => {}
+                    Some(group) => {
+                        groups.push(i);
+                        ret.names.push(group.to_owned());
+                    }
+                }
+            }
+            if groups.is_empty() {
+                return Err(AppError::MissingMetricGroup(pattern.clone()));
+            }
+
+            ret.patterns.push((re, groups));
+        }
+
+        Ok(ret)
+    }
+
+    /// Names of the metrics, in the same order [`MetricParser::parse`] returns them
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn parse(&self, output: &str, players: &[String; 4]) -> Vec<[f64; 4]> {
+        let mut ret = vec![[0.; 4]; self.names.len()];
+
+        let mut first_metric = 0;
+        for (re, groups) in &self.patterns {
+            let mut assigned = [false; 4];
+            for caps in re.captures_iter(output) {
+                let Some(seat) = caps
+                    .name("name")
+                    .and_then(|name| next_seat(players, &mut assigned, name.as_str()))
+                else {
+                    continue;
+                };
+                for (metric, &group) in groups.iter().enumerate() {
+                    if let Some(m) = caps.get(group) {
+                        ret[first_metric + metric][seat] += m.as_str().parse().unwrap_or(1.);
+                    }
+                }
+            }
+            first_metric += groups.len();
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,169 @@
+//! What a run would do, printed by `--dry-run` instead of playing anything.
+
+use crate::errors::AppError;
+use crate::runner::SettingsVia;
+use crate::sweep;
+use crate::{PlayerName, TestConfig};
+
+use color_eyre::eyre::Result;
+use std::{ffi::OsStr, fs, process::Command};
+
+/// Quotes a word the way a shell would need it, if it needs it at all
+fn quote(word: &OsStr) -> String {
+    let word = word.to_string_lossy();
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.into_owned()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Command line of a game, as it would be typed in a shell
+pub fn command_line(config: &TestConfig) -> String {
+    let mut args: Vec<String> = config.players.iter().map(PlayerName::as_string).collect();
+    args.extend(["-s".to_owned(), "SEED".to_owned()]);
+    if config.settings_via == SettingsVia::Arg {
+        args.extend(["-i".to_owned(), "settings".to_owned()]);
+    }
+    let command = if let Some(sandbox) = &config.sandbox {
+        sandbox.command(&config.game, &args, None)
+    } else {
+        let mut command = Command::new(&config.game);
+        command.args(&args);
+        command
+    };
+
+    let mut words: Vec<_> = config
+        .env
+        .iter()
+        .map(|(key, value)| quote(format!("{key}={value}").as_ref()))
+        .collect();
+    words.push(quote(command.get_program()));
+    words.extend(command.get_args().map(quote));
+    if config.settings_via != SettingsVia::Arg {
+        words.push("< settings".to_owned());
+    }
+    words.join(" ")
+}
+
+fn parallelism(config: &TestConfig, jobs: usize) -> String {
+    if let Some(serve) = &config.serve_jobs {
+        format!(
+            "handed out in batches of {} to the workers that connect to {}",
+            serve.batch, serve.listen
+        )
+    } else if !config.workers.is_empty() {
+        format!(
+            "{jobs}, spread over {} through ssh",
+            config.workers.join(", ")
+        )
+    } else if let Some(target) = config.load_target {
+        format!("up to {jobs}, fewer if that would keep more than {target} CPUs busy")
+    } else {
+        jobs.to_string()
+    }
+}
+
+fn outputs(config: &TestConfig) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(dir) = &config.artifacts {
+        lines.push(format!(
+            "Run artifacts: in a new timestamped folder inside {}",
+            dir.display()
+        ));
+    }
+    match &config.save_output {
+        Some(dir) => lines.push(format!("Game outputs: {}/SEED.res", dir.display())),
+        None if config.artifacts.is_some() => {
+            lines.push("Game outputs: in the replays of the run artifacts".to_owned());
+        }
+        None => lines.push("Game outputs: not saved".to_owned()),
+    }
+    if let Some(path) = &config.stream {
+        let path = if path.as_os_str() == "-" {
+            "stdout".into()
+        } else {
+            path.display().to_string()
+        };
+        lines.push(format!("Per-game results: {path}"));
+    }
+    if let Some(path) = &config.progress_json {
+        lines.push(format!("Progress events: {}", path.display()));
+    }
+    if let Some(addr) = &config.metrics_addr {
+        lines.push(format!("Prometheus metrics: http://{addr}/metrics"));
+    }
+    if let Some((url, _)) = &config.webhook {
+        lines.push(format!("Summary posted to {url}"));
+    }
+    lines
+}
+
+/// Prints what running the games of `config` would do
+pub fn print(config: &TestConfig, jobs: usize) -> Result<()> {
+    let last = config
+        .seed
+        .checked_add(config.instances.get() - 1)
+        .ok_or(AppError::SeedRangeOutOfBounds)?;
+    let games = u64::from(config.instances.get()) * u64::from(config.repeat.get());
+    let repeat = match config.repeat.get() {
+        1 => "once".to_owned(),
+        times => format!("{times} times"),
+    };
+    let mut settings = fs::read_to_string(&config.settings_file)
+        .map_err(|_| AppError::MissingFile(config.settings_file.clone().into()))?;
+    if !config.settings.is_empty() {
+        settings = sweep::override_settings(&settings, &config.settings);
+    }
+
+    let mut lines = vec![
+        "Dry run, nothing will be played".to_owned(),
+        format!("Command: {}", command_line(config)),
+        format!(
+            "Seeds: {} to {last}, each played {repeat}: {games} games",
+            config.seed
+        ),
+        format!("Games at once: {}", parallelism(config, jobs)),
+        format!(
+            "Game timeout: {}",
+            config
+                .game_timeout
+                .map_or_else(|| "none".to_owned(), |t| format!("{}s", t.as_secs()))
+        ),
+    ];
+    if let Some(budget) = &config.cpu_budget {
+        let enforced = if budget.enforce {
+            ", killing the games over it"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "CPU budget: {:.2?} per game on the judge, {:.2?} here{enforced}",
+            budget.budget,
+            budget.local()
+        ));
+    }
+    if config.isolate {
+        lines.push("Every game runs in a private copy of the current directory".to_owned());
+    }
+    if let Some(hook) = &config.pre_game {
+        lines.push(format!("Before every game: sh -c {hook:?} sh SEED"));
+    }
+    if let Some(hook) = &config.post_game {
+        lines.push(format!("After every game: sh -c {hook:?} sh SEED"));
+    }
+    lines.extend(outputs(config));
+    lines.push(if config.settings.is_empty() {
+        format!("Settings, from {}:", config.settings_file)
+    } else {
+        format!(
+            "Settings, from {} with the --setting values:",
+            config.settings_file
+        )
+    });
+    lines.extend(settings.lines().map(|line| format!("    {line}")));
+
+    println!("{}", lines.join("\n"));
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! What makes a run fail, for the exit code of the process.
+
+use crate::errors::AppError;
+use crate::history;
+use crate::results::TestResults;
+use crate::TestConfig;
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum FailOn {
+    /// Only fail if the tester itself fails
+    #[default]
+    Never,
+    /// Fail if any game crashed or couldn't be started
+    Crashes,
+    /// Fail if the focal player's win rate dropped from the --baseline run
+    Regression,
+    /// Fail if the focal player is below --min-win-rate or --min-score
+    Thresholds,
+}
+
+#[derive(Clone, Default)]
+pub struct Policy {
+    pub fail_on: FailOn,
+    /// Saved run (`--artifacts`) the regressions are measured against
+    pub baseline: Option<PathBuf>,
+    /// Percentage points the win rate can drop before it counts as a regression
+    pub max_regression: f64,
+    pub min_win_rate: Option<f64>,
+    pub min_score: Option<f64>,
+}
+
+impl Policy {
+    /// Checks the policy has everything it needs
+    pub fn validate(&self) -> Result<(), AppError> {
+        match self.fail_on {
+            FailOn::Regression if self.baseline.is_none() => Err(AppError::MissingBaseline),
+            FailOn::Thresholds if self.min_win_rate.is_none() && self.min_score.is_none() => {
+                Err(AppError::MissingThresholds)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reasons for the run to fail, if any
+    pub fn failures(&self, config: &TestConfig, results: &TestResults) -> Result<Vec<String>> {
+        let seat = config.focus.unwrap_or(0);
+        let name = config.players[seat].as_string();
+        let games = f64::from(results.ok_games().max(1));
+        let win_rate = f64::from(results.player_results[seat].total_wins) * 100. / games;
+
+        let mut ret = Vec::new();
+        match self.fail_on {
+            FailOn::Never => {}
+            FailOn::Crashes => {
+                if !results.failed_seeds.is_empty() {
+                    ret.push(format!("{} games crashed", results.failed_seeds.len()));
+                }
+                if !results.parse_error_seeds.is_empty() {
+                    ret.push(format!(
+                        "{} games had scores the score regex didn't find",
+                        results.parse_error_seeds.len()
+                    ));
+                }
+                if !results.unstarted_seeds.is_empty() {
+                    ret.push(format!(
+                        "{} games couldn't be started",
+                        results.unstarted_seeds.len()
+                    ));
+                }
+            }
+            FailOn::Regression => {
+                let Some(baseline) = &self.baseline else {
+                    return Err(AppError::MissingBaseline.into());
+                };
+                let (old, new) = history::win_rates(baseline, results, seat)?;
+                if old - new > self.max_regression {
+                    ret.push(format!(
+                        "{name} won {new:.1}% of the common seeds, down from {old:.1}% in {}",
+                        baseline.display()
+                    ));
+                }
+            }
+            FailOn::Thresholds => {
+                if let Some(min) = self.min_win_rate.filter(|&min| win_rate < min) {
+                    ret.push(format!(
+                        "{name} won {win_rate:.1}% of its games, below --min-win-rate {min}"
+                    ));
+                }
+                let average = f64::from(results.player_results[seat].total_points) / games;
+                if let Some(min) = self.min_score.filter(|&min| average < min) {
+                    ret.push(format!(
+                        "{name} got {average:.1} points in average, below --min-score {min}"
+                    ));
+                }
+            }
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,292 @@
+use crate::json::Json;
+use crate::results::LiveStats;
+use crate::runner::{ExecutionResults, GameObserver};
+use crate::signals;
+
+use color_eyre::eyre::Result;
+use indicatif::ProgressBar;
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// Percentage of the run between two progress lines
+const STEP_PERCENT: u32 = 5;
+
+/// Maximum time between two progress lines
+const STEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often `SIGUSR1` is checked for during a run
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+struct PlainState {
+    finished: u32,
+    last_percent: u32,
+    last_print: Instant,
+}
+
+/// Progress reporting for logs: prints a single line every few percent or seconds instead of
+/// redrawing a bar
+pub struct PlainProgress {
+    total: u32,
+    start: Instant,
+    state: Mutex<PlainState>,
+}
+
+impl PlainProgress {
+    pub fn new(total: u32) -> Self {
+        let start = Instant::now();
+        eprintln!("Running {total} games...");
+        Self {
+            total,
+            start,
+            state: Mutex::new(PlainState {
+                finished: 0,
+                last_percent: 0,
+                last_print: start,
+            }),
+        }
+    }
+}
+
+impl GameObserver for PlainProgress {
+    fn game_finished(&self, _result: &ExecutionResults) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.finished += 1;
+
+        #[allow(clippy::cast_possible_truncation)] // Correctness: finished <= total
+        let percent = (u64::from(state.finished) * 100 / u64::from(self.total)) as u32;
+        let now = Instant::now();
+        if percent / STEP_PERCENT == state.last_percent / STEP_PERCENT
+            && now - state.last_print < STEP_INTERVAL
+            && state.finished < self.total
+        {
+            return Ok(());
+        }
+        state.last_percent = percent;
+        state.last_print = now;
+
+        let elapsed = self.start.elapsed().as_secs_f64();
+        eprintln!(
+            "Progress: {}/{} games ({percent}%), {:.1} games/s, {elapsed:.0}s elapsed",
+            state.finished,
+            self.total,
+            f64::from(state.finished) / elapsed.max(f64::EPSILON),
+        );
+        Ok(())
+    }
+}
+
+/// When the interim summaries are printed, whichever comes first
+#[derive(Clone, Copy, Default)]
+pub struct SummaryEvery {
+    pub games: Option<NonZeroU32>,
+    pub interval: Option<Duration>,
+}
+
+struct SummaryState {
+    stats: LiveStats,
+    last_games: u32,
+    last_print: Instant,
+}
+
+/// Running averages, win rates and crashes printed every few games or seconds, which gives an
+/// early idea of the results of long runs, even in logs. They're also reported on `SIGUSR1`
+pub struct InterimSummary {
+    names: [String; 4],
+    total: u32,
+    every: SummaryEvery,
+    /// File the summaries asked for with `SIGUSR1` are written to, instead of printing them
+    stats_file: Option<PathBuf>,
+    /// Prints above the bar, as printing anywhere else would break it
+    pb: ProgressBar,
+    state: Mutex<SummaryState>,
+    finished: AtomicBool,
+}
+
+impl InterimSummary {
+    pub fn new(
+        names: &[String; 4],
+        total: u32,
+        every: SummaryEvery,
+        stats_file: Option<PathBuf>,
+        pb: ProgressBar,
+    ) -> Self {
+        Self {
+            names: names.clone(),
+            total,
+            every,
+            stats_file,
+            pb,
+            state: Mutex::new(SummaryState {
+                stats: LiveStats::default(),
+                last_games: 0,
+                last_print: Instant::now(),
+            }),
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SummaryState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lines(&self, stats: &LiveStats) -> Vec<String> {
+        let finished = stats.games + stats.crashes;
+        let games = f64::from(stats.games.max(1));
+        let mut lines = vec![format!(
+            "After {finished}/{} games ({} crashed):",
+            self.total, stats.crashes
+        )];
+        for i in 0..4 {
+            #[allow(clippy::cast_precision_loss)] // Correctness: Only used for display
+            let average = stats.points[i] as f64 / games;
+            lines.push(format!(
+                "  {:<12} {average:>9.1} points in average {:>6.1}% WR",
+                self.names[i],
+                f64::from(stats.wins[i]) * 100. / games,
+            ));
+        }
+        lines
+    }
+
+    fn print(&self, stats: &LiveStats) {
+        let lines = self.lines(stats).join("\n");
+        // A hidden bar doesn't print anything
+        if self.pb.is_hidden() {
+            eprintln!("{lines}");
+        } else {
+            self.pb.println(lines);
+        }
+    }
+
+    /// Reports the summary every time `SIGUSR1` is received, until the run finishes
+    pub fn watch_signal(self: &Arc<Self>) {
+        let summary = Arc::downgrade(self);
+        thread::spawn(move || loop {
+            thread::sleep(SIGNAL_POLL_INTERVAL);
+            let Some(summary) = summary.upgrade() else {
+                return;
+            };
+            if summary.finished.load(Ordering::Relaxed) {
+                return;
+            }
+            if !signals::take_stats_request() {
+                continue;
+            }
+            let state = summary.lock();
+            if let Some(path) = &summary.stats_file {
+                let lines = summary.lines(&state.stats).join("\n") + "\n";
+                if let Err(err) = fs::write(path, lines) {
+                    warn!(path = %path.display(), %err, "Couldn't write the statistics");
+                }
+            } else {
+                summary.print(&state.stats);
+            }
+        });
+    }
+}
+
+impl GameObserver for InterimSummary {
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()> {
+        let mut state = self.lock();
+        state.stats.record(result);
+        let finished = state.stats.games + state.stats.crashes;
+        let now = Instant::now();
+
+        // The final results come right after the last game
+        let due = self
+            .every
+            .games
+            .is_some_and(|n| finished - state.last_games >= n.get())
+            || self
+                .every
+                .interval
+                .is_some_and(|t| now - state.last_print >= t);
+        if due && finished < self.total {
+            state.last_games = finished;
+            state.last_print = now;
+            self.print(&state.stats);
+        }
+        Ok(())
+    }
+
+    fn run_finished(&self) -> Result<()> {
+        self.finished.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Progress events for wrapper scripts, written as one JSON object per line.
+///
+/// Events are `run_started`, `game_started`, `game_finished` (with the percent complete) and
+/// `run_finished`. Any path works, so `/dev/fd/N` can be used to write to an inherited fd.
+pub struct ProgressEvents {
+    out: Mutex<(BufWriter<File>, u32)>,
+    total: u32,
+}
+
+impl ProgressEvents {
+    pub fn open(path: &Path, total: u32) -> Result<Self> {
+        let ret = Self {
+            out: Mutex::new((BufWriter::new(File::create(path)?), 0)),
+            total,
+        };
+        ret.emit(|_| Json::object([("event", "run_started".into()), ("total", total.into())]))?;
+        Ok(ret)
+    }
+
+    /// Writes the event built from the number of finished games
+    fn emit(&self, event: impl FnOnce(&mut u32) -> Json) -> Result<()> {
+        let mut out = self.out.lock().unwrap_or_else(PoisonError::into_inner);
+        let (writer, finished) = &mut *out;
+        let event = event(finished);
+        writeln!(writer, "{event}")?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl GameObserver for ProgressEvents {
+    fn game_started(&self, seed: u32) -> Result<()> {
+        self.emit(|_| Json::object([("event", "game_started".into()), ("seed", seed.into())]))
+    }
+
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()> {
+        self.emit(|finished| {
+            *finished += 1;
+            Json::object([
+                ("event", "game_finished".into()),
+                ("seed", result.seed().into()),
+                (
+                    "crashed",
+                    matches!(result, ExecutionResults::Crash { .. }).into(),
+                ),
+                ("finished", (*finished).into()),
+                ("total", self.total.into()),
+                (
+                    "percent",
+                    (f64::from(*finished) * 100. / f64::from(self.total)).into(),
+                ),
+            ])
+        })
+    }
+
+    fn run_finished(&self) -> Result<()> {
+        self.emit(|finished| {
+            Json::object([
+                ("event", "run_finished".into()),
+                ("finished", (*finished).into()),
+            ])
+        })
+    }
+}
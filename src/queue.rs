@@ -0,0 +1,249 @@
+//! Runs queued for `daemon` to play in the background, saving them with `--artifacts` so they show
+//! up in the history. The queue is a folder of the runs directory with a file per request: its
+//! working directory on the first line and then an argument of `run` per line, moved from
+//! `pending` to `running` and then to `done` or `failed` next to the log of its output.
+
+use crate::artifacts;
+use crate::errors::AppError;
+
+use color_eyre::eyre::Result;
+use std::{
+    env,
+    fs::{self, File},
+    num::NonZeroUsize,
+    path::{self, Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// How often the daemon looks for new requests and finished runs
+const POLL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl State {
+    const ALL: [Self; 4] = [Self::Pending, Self::Running, Self::Done, Self::Failed];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+pub struct Request {
+    pub id: String,
+    pub state: State,
+    pub dir: PathBuf,
+    pub args: Vec<String>,
+}
+
+pub struct Queue {
+    dir: PathBuf,
+}
+
+impl Queue {
+    pub fn new(runs_dir: &Path) -> Self {
+        Self {
+            dir: runs_dir.join("queue"),
+        }
+    }
+
+    fn file(&self, state: State, id: &str, extension: &str) -> PathBuf {
+        self.dir
+            .join(state.name())
+            .join(format!("{id}.{extension}"))
+    }
+
+    /// Queues a run with these arguments, played from `dir`, returning its id
+    pub fn add(&self, dir: &Path, args: &[String]) -> Result<String> {
+        if let Some(arg) = args.iter().find(|a| a.contains('\n')) {
+            return Err(AppError::InvalidQueueArgument(arg.clone()).into());
+        }
+        let pending = self.dir.join(State::Pending.name());
+        fs::create_dir_all(&pending)?;
+
+        // The ids sort in the order the requests were queued
+        let stamp = artifacts::timestamp();
+        let mut id = stamp.clone();
+        let mut suffix = 1;
+        while State::ALL
+            .iter()
+            .any(|&state| self.file(state, &id, "args").exists())
+        {
+            suffix += 1;
+            id = format!("{stamp}-{suffix:03}");
+        }
+
+        let mut lines = vec![dir.to_string_lossy().into_owned()];
+        lines.extend(args.iter().cloned());
+        fs::write(
+            self.file(State::Pending, &id, "args"),
+            lines.join("\n") + "\n",
+        )?;
+        Ok(id)
+    }
+
+    /// Every request in the queue, oldest first in each state
+    pub fn list(&self) -> Vec<Request> {
+        let mut ret = Vec::new();
+        for state in State::ALL {
+            let mut ids: Vec<String> = fs::read_dir(self.dir.join(state.name()))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    name.strip_suffix(".args").map(str::to_owned)
+                })
+                .collect();
+            ids.sort();
+            for id in ids {
+                let Ok(contents) = fs::read_to_string(self.file(state, &id, "args")) else {
+                    continue;
+                };
+                let mut lines = contents.lines().map(str::to_owned);
+                let dir = PathBuf::from(lines.next().unwrap_or_default());
+                ret.push(Request {
+                    id,
+                    state,
+                    dir,
+                    args: lines.collect(),
+                });
+            }
+        }
+        ret
+    }
+
+    /// Moves a request and its log to another state
+    fn advance(&self, id: &str, from: State, to: State) -> Result<()> {
+        fs::create_dir_all(self.dir.join(to.name()))?;
+        fs::rename(self.file(from, id, "args"), self.file(to, id, "args"))?;
+        let log = self.file(from, id, "log");
+        if log.exists() {
+            fs::rename(log, self.file(to, id, "log"))?;
+        }
+        Ok(())
+    }
+
+    /// Starts the oldest pending request that can be started, saving its results to `runs_dir`
+    fn start(
+        &self,
+        runs_dir: &Path,
+        run_jobs: Option<NonZeroUsize>,
+    ) -> Result<Option<(String, Child)>> {
+        for request in self
+            .list()
+            .into_iter()
+            .filter(|r| r.state == State::Pending)
+        {
+            // Renaming is atomic, so another daemon on the same queue can't start it too
+            if self
+                .advance(&request.id, State::Pending, State::Running)
+                .is_err()
+            {
+                continue;
+            }
+            if let Some(child) = self.spawn(&request, runs_dir, run_jobs)? {
+                return Ok(Some((request.id, child)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Plays a request that was just moved to `running`, moving it to `failed` if it can't start
+    fn spawn(
+        &self,
+        request: &Request,
+        runs_dir: &Path,
+        run_jobs: Option<NonZeroUsize>,
+    ) -> Result<Option<Child>> {
+        let log = File::create(self.file(State::Running, &request.id, "log"))?;
+        let mut command = Command::new(env::current_exe()?);
+        command.arg("run").args(&request.args);
+        if let Some(jobs) = run_jobs {
+            command.arg("--jobs").arg(jobs.to_string());
+        }
+        if !request
+            .args
+            .iter()
+            .any(|a| a == "--artifacts" || a.starts_with("--artifacts="))
+        {
+            command.arg("--artifacts").arg(path::absolute(runs_dir)?);
+        }
+        info!(id = request.id, ?command, "Starting queued run");
+        println!("Starting {}: {}", request.id, request.args.join(" "));
+        let spawned = command
+            .current_dir(&request.dir)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .spawn();
+        match spawned {
+            Ok(child) => Ok(Some(child)),
+            Err(err) => {
+                warn!(id = request.id, %err, "Couldn't start the queued run");
+                self.advance(&request.id, State::Running, State::Failed)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Plays the queued runs, `parallel` at a time with `run_jobs` games each, until stopped or, with
+/// `until_empty`, until there's nothing left to play
+pub fn daemon(
+    runs_dir: &Path,
+    parallel: NonZeroUsize,
+    run_jobs: Option<NonZeroUsize>,
+    until_empty: bool,
+) -> Result<()> {
+    let queue = Queue::new(runs_dir);
+    println!(
+        "Playing the runs queued in {}, {parallel} at a time",
+        queue.dir.display()
+    );
+    let mut running: Vec<(String, Child)> = Vec::new();
+    loop {
+        let mut still_running = Vec::new();
+        for (id, mut child) in running {
+            match child.try_wait()? {
+                None => still_running.push((id, child)),
+                Some(status) => {
+                    let state = if status.success() {
+                        State::Done
+                    } else {
+                        warn!(id, %status, "Queued run failed");
+                        State::Failed
+                    };
+                    println!("Finished {id}: {}", state.name());
+                    queue.advance(&id, State::Running, state)?;
+                }
+            }
+        }
+        running = still_running;
+
+        while running.len() < parallel.get() {
+            match queue.start(runs_dir, run_jobs)? {
+                Some(started) => running.push(started),
+                None => break,
+            }
+        }
+        if running.is_empty() && until_empty {
+            println!("The queue is empty");
+            return Ok(());
+        }
+        thread::sleep(POLL);
+    }
+}
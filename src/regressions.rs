@@ -0,0 +1,85 @@
+//! The seeds where a player placed worse than in an earlier run, from the biggest score drop, to
+//! turn a lower average into a list of games to look at.
+
+use crate::results::TestResults;
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// Scores of every seed that didn't crash
+pub type Scores = HashMap<u32, [u32; 4]>;
+
+pub fn scores(results: &TestResults) -> Scores {
+    results
+        .games
+        .iter()
+        .filter_map(|game| Some((game.seed, game.points?)))
+        .collect()
+}
+
+/// Place of `seat` in a game, sharing the best one with the players it ties with
+fn place(scores: &[u32; 4], seat: usize) -> usize {
+    1 + scores.iter().filter(|&&p| p > scores[seat]).count()
+}
+
+fn ordinal(place: usize) -> &'static str {
+    ["1st", "2nd", "3rd", "4th"][place - 1]
+}
+
+/// A seed where the player placed worse than before
+pub struct Drop {
+    pub seed: u32,
+    places: (usize, usize),
+    points: (u32, u32),
+}
+
+impl Drop {
+    fn delta(&self) -> i64 {
+        i64::from(self.points.1) - i64::from(self.points.0)
+    }
+}
+
+/// Seeds both runs played where `seat` placed worse in `new`, the biggest score drop first
+pub fn find(old: &Scores, new: &Scores, seat: usize) -> Vec<Drop> {
+    let mut ret: Vec<_> = new
+        .iter()
+        .filter_map(|(&seed, new)| {
+            let old = old.get(&seed)?;
+            let places = (place(old, seat), place(new, seat));
+            (places.1 > places.0).then_some(Drop {
+                seed,
+                places,
+                points: (old[seat], new[seat]),
+            })
+        })
+        .collect();
+    ret.sort_unstable_by_key(|drop| (drop.delta(), drop.seed));
+    ret
+}
+
+/// Writes the first `count` drops of `player`
+pub fn write(out: &mut impl Write, drops: &[Drop], player: &str, count: usize) -> io::Result<()> {
+    if drops.is_empty() {
+        return writeln!(out, "{player} didn't place worse on any seed");
+    }
+    writeln!(
+        out,
+        "{player} placed worse on {} seeds, the biggest score drops first:",
+        drops.len()
+    )?;
+    for drop in drops.iter().take(count) {
+        writeln!(
+            out,
+            "=> {}: {} to {}, {} to {} points ({:+})",
+            drop.seed,
+            ordinal(drop.places.0),
+            ordinal(drop.places.1),
+            drop.points.0,
+            drop.points.1,
+            drop.delta()
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::errors::AppError;
+
+use color_eyre::eyre::Result;
+use std::{
+    path::Path,
+    process::{Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// Quotes a word for the remote shell
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+fn ssh(host: &str) -> Command {
+    let mut command = Command::new("ssh");
+    command.args(["-o", "BatchMode=yes", host]);
+    command
+}
+
+fn check(host: &str, output: &Output) -> Result<String> {
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        return Err(AppError::WorkerSetup(host.to_owned(), stderr).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+struct Worker {
+    host: String,
+    dir: String,
+}
+
+/// Remote machines the games are spread across, each with its own copy of the game binary.
+///
+/// The temporary directories holding the copies are removed when this is dropped.
+pub struct Workers {
+    workers: Vec<Worker>,
+    next: AtomicUsize,
+}
+
+impl Workers {
+    /// Ships the `game` binary to a temporary directory on every host, as `Game`
+    pub fn setup(hosts: &[String], game: &Path) -> Result<Self> {
+        // Workers get registered before copying anything, so dropping this on failure cleans up
+        // the directories created so far
+        let mut ret = Self {
+            workers: Vec::new(),
+            next: AtomicUsize::new(0),
+        };
+        for host in hosts {
+            let dir = check(
+                host,
+                &ssh(host)
+                    .args(["mktemp", "-d", "-t", "eda-game-tester.XXXXXX"])
+                    .output()?,
+            )?;
+            ret.workers.push(Worker {
+                host: host.clone(),
+                dir: dir.clone(),
+            });
+
+            check(
+                host,
+                &Command::new("scp")
+                    .args(["-q", "-o", "BatchMode=yes"])
+                    .arg(game)
+                    .arg(format!("{host}:{dir}/Game"))
+                    .output()?,
+            )?;
+            info!(host, dir, "Worker ready");
+        }
+
+        Ok(ret)
+    }
+
+    /// Command running the game on the next worker, in turns. Killing it only stops the local
+    /// `ssh`, so the game is also killed on the worker once it runs for longer than `timeout`
+    pub fn command(
+        &self,
+        args: &[String],
+        env: &[(String, String)],
+        timeout: Option<Duration>,
+    ) -> (&str, Command) {
+        let worker = &self.workers[self.next.fetch_add(1, Ordering::Relaxed) % self.workers.len()];
+        let args: Vec<_> = args.iter().map(|a| quote(a)).collect();
+        let env: Vec<_> = env
+            .iter()
+            .map(|(key, value)| quote(&format!("{key}={value}")))
+            .collect();
+
+        let timeout = timeout.map_or_else(String::new, |t| {
+            format!("timeout -s KILL {:.3} ", t.as_secs_f64())
+        });
+
+        let mut command = ssh(&worker.host);
+        command.arg(format!(
+            "cd {} && exec {timeout}env {} ./Game {}",
+            quote(&worker.dir),
+            env.join(" "),
+            args.join(" ")
+        ));
+        (&worker.host, command)
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        for worker in &self.workers {
+            let removed = ssh(&worker.host)
+                .args(["rm", "-rf", &quote(&worker.dir)])
+                .output()
+                .is_ok_and(|o| o.status.success());
+            if !removed {
+                warn!(
+                    host = worker.host,
+                    dir = worker.dir,
+                    "Couldn't clean up worker"
+                );
+            }
+        }
+    }
+}
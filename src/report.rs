@@ -0,0 +1,776 @@
+use crate::bootstrap;
+use crate::cpu_budget::CpuBudget;
+use crate::errors::AppError;
+use crate::flaky;
+use crate::focus;
+use crate::margins::MarginStats;
+use crate::results::{GameRecord, PlayerResults, TestResults};
+use crate::teams::Teams;
+use crate::timing::{CpuStats, DurationStats, MemoryStats};
+use crate::TestConfig;
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use std::io::{self, Write};
+
+/// Writes the final summary of the run, highlighting the best player with `color`
+pub fn write_results(
+    out: &mut impl Write,
+    config: &TestConfig,
+    results: &TestResults,
+    metric_names: &[String],
+    color: bool,
+) -> io::Result<()> {
+    write_players(out, config, results, metric_names, color)?;
+
+    if let Some(stats) = DurationStats::compute(results) {
+        write_durations(out, &stats, CpuStats::compute(results))?;
+    }
+
+    if let Some(budget) = &config.cpu_budget {
+        write_cpu_budget(out, budget, results)?;
+    }
+
+    if let Some(stats) = MemoryStats::compute(results) {
+        write_memory(out, config, &stats)?;
+    }
+
+    if let Some(seat) = config.focus {
+        write_focus(out, config, results, seat)?;
+    }
+
+    if let Some(teams) = config.teams {
+        write_teams(out, config, results, teams)?;
+    }
+
+    if config.mirror {
+        write_mirror(out, config, results)?;
+    }
+
+    if config.repeat.get() > 1 {
+        write_flaky(out, config, results)?;
+    }
+
+    if !results.failed_seeds.is_empty() {
+        writeln!(out, "Some games crashed! Faulty seeds:")?;
+        for seed in results.failed_seeds.iter().sorted().dedup() {
+            writeln!(out, "=> {seed}")?;
+        }
+        writeln!(out, "Crashes by kind:")?;
+        for (kind, games) in results.crash_kinds() {
+            writeln!(out, "=> {kind}: {games}")?;
+        }
+    }
+
+    if !results.parse_error_seeds.is_empty() {
+        writeln!(
+            out,
+            "The score regex didn't find every score in some games, left out of the results:"
+        )?;
+        for seed in results.parse_error_seeds.iter().sorted().dedup() {
+            writeln!(out, "=> {seed}")?;
+        }
+    }
+
+    if !results.unstarted_seeds.is_empty() {
+        writeln!(out, "Some games couldn't be started, even after retrying:")?;
+        for seed in results.unstarted_seeds.iter().sorted().dedup() {
+            writeln!(out, "=> {seed}")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// One line per run, to compare the players of several runs at a glance
+pub fn write_groups(
+    out: &mut impl Write,
+    groups: &[(String, [String; 4], TestResults)],
+) -> io::Result<()> {
+    writeln!(out, "Summary:")?;
+    for (label, players, results) in groups {
+        let games = f64::from(results.ok_games().max(1));
+        let players: Vec<_> = players
+            .iter()
+            .zip(&results.player_results)
+            .map(|(name, r)| {
+                format!(
+                    "{name} {:.1} ({:.1}% WR)",
+                    f64::from(r.total_points) / games,
+                    f64::from(r.total_wins) * 100. / games
+                )
+            })
+            .collect();
+        let mut parts = vec![players.join(", ")];
+        if !results.failed_seeds.is_empty() {
+            parts.push(format!("{} crashed", results.failed_seeds.len()));
+        }
+        writeln!(out, "=> {label}: {}", parts.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Seats of every distinct player name, in order of appearance
+fn seats_by_player(config: &TestConfig) -> Vec<(String, Vec<usize>)> {
+    let mut players: Vec<(String, Vec<usize>)> = Vec::new();
+    for (seat, player) in config.players.iter().enumerate() {
+        let name = player.as_string();
+        match players.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, seats)) => seats.push(seat),
+            None => players.push((name, vec![seat])),
+        }
+    }
+    players
+}
+
+/// Score relative to the other seats of the same game, so that seeds with very high or very low
+/// scores weigh the same as the rest
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Normalization {
+    /// Share of the total points of the game
+    Share,
+    /// Standard deviations above the mean score of the game
+    Zscore,
+}
+
+impl Normalization {
+    fn apply(self, points: [u32; 4]) -> [f64; 4] {
+        let points = points.map(f64::from);
+        let total: f64 = points.iter().sum();
+        match self {
+            Self::Share if total == 0. => [0.25; 4],
+            Self::Share => points.map(|p| p / total),
+            Self::Zscore => {
+                let mean = total / 4.;
+                let deviation =
+                    (points.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / 4.).sqrt();
+                if deviation == 0. {
+                    [0.; 4]
+                } else {
+                    points.map(|p| (p - mean) / deviation)
+                }
+            }
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            Self::Share => "Share",
+            Self::Zscore => "Z-score",
+        }
+    }
+
+    fn format(self, value: f64) -> String {
+        match self {
+            Self::Share => format!("{:.1}%", value * 100.),
+            Self::Zscore => format!("{value:+.2}"),
+        }
+    }
+}
+
+/// Order of the players in the summary
+#[derive(Clone, Copy, Debug)]
+pub enum SortBy {
+    WinRate,
+    Score,
+    Seat,
+    /// Rate of top-K finishes
+    Top,
+    /// The score given by `--normalize`
+    Normalized,
+    /// Index of a `--metric` group
+    Metric(usize),
+}
+
+impl SortBy {
+    /// Reads `wr`, `score`, `seat`, `top`, `norm` (only if the scores are normalized) or the name
+    /// of one of the metrics
+    pub fn parse(key: &str, metric_names: &[String], normalized: bool) -> Result<Self, AppError> {
+        match key {
+            "wr" => Ok(Self::WinRate),
+            "score" => Ok(Self::Score),
+            "seat" => Ok(Self::Seat),
+            "top" => Ok(Self::Top),
+            "norm" if normalized => Ok(Self::Normalized),
+            _ => metric_names
+                .iter()
+                .position(|name| name == key)
+                .map(Self::Metric)
+                .ok_or_else(|| AppError::UnknownSortKey(key.to_owned())),
+        }
+    }
+}
+
+/// Summary of a player over all of its seats
+struct PlayerRow {
+    name: String,
+    seats: Vec<usize>,
+    average: f64,
+    /// Bootstrap confidence interval of the average
+    interval: Option<(f64, f64)>,
+    win_rate: f64,
+    /// Percentage of the games finished in the top `config.top` places
+    top_rate: f64,
+    /// Average normalized score, with `--normalize`
+    normalized: Option<f64>,
+    metrics: Vec<f64>,
+}
+
+impl PlayerRow {
+    fn seat_count(&self) -> f64 {
+        f64::from(u32::try_from(self.seats.len()).unwrap_or(4))
+    }
+
+    /// Name of the player, along with its seats if it has several
+    fn label(&self) -> String {
+        if self.seats.len() == 1 {
+            self.name.clone()
+        } else {
+            let list: Vec<_> = self.seats.iter().map(|s| (s + 1).to_string()).collect();
+            format!("{} (seats {})", self.name, list.join(", "))
+        }
+    }
+}
+
+impl SortBy {
+    fn key(self, row: &PlayerRow) -> f64 {
+        match self {
+            Self::WinRate => row.win_rate,
+            Self::Score => row.average,
+            Self::Seat => 0.,
+            Self::Top => row.top_rate,
+            Self::Normalized => row.normalized.unwrap_or_default(),
+            Self::Metric(i) => row.metrics[i],
+        }
+    }
+}
+
+/// Rows of the players, in the order of `--sort-by`
+fn sorted_rows(
+    config: &TestConfig,
+    results: &TestResults,
+    metric_names: &[String],
+) -> Vec<PlayerRow> {
+    let mut rows = player_rows(config, results, metric_names);
+    // Stable, so ties and `seat` keep the seat order
+    rows.sort_by(|a, b| config.sort_by.key(b).total_cmp(&config.sort_by.key(a)));
+    rows
+}
+
+fn player_rows(
+    config: &TestConfig,
+    results: &TestResults,
+    metric_names: &[String],
+) -> Vec<PlayerRow> {
+    let ok_games = results.ok_games();
+
+    // A player in several seats gets the totals of all of them, averaged per seat, and wins the
+    // games any of its seats wins
+    seats_by_player(config)
+        .into_iter()
+        .map(|(name, seats)| {
+            let seat_games =
+                f64::from(ok_games) * f64::from(u32::try_from(seats.len()).unwrap_or(4));
+            let seat_results = || seats.iter().map(|&seat| &results.player_results[seat]);
+            let wins = if seats.len() == 1 {
+                results.player_results[seats[0]].total_wins
+            } else {
+                let won = |game: &GameRecord| {
+                    game.winners()
+                        .is_some_and(|winners| seats.iter().any(|&s| winners[s]))
+                };
+                u32::try_from(results.games.iter().filter(|g| won(g)).count()).unwrap_or(u32::MAX)
+            };
+            let metrics = (0..metric_names.len())
+                .map(|i| {
+                    seat_results()
+                        .map(|r| r.metrics.get(i).copied().unwrap_or_default())
+                        .sum::<f64>()
+                        / seat_games
+                })
+                .collect();
+            // A game's place is one more than the seats that scored more, so ties share it
+            let top = results
+                .games
+                .iter()
+                .filter_map(|g| g.points)
+                .filter(|points| {
+                    seats.iter().any(|&s| {
+                        points.iter().filter(|&&p| p > points[s]).count() < usize::from(config.top)
+                    })
+                })
+                .count();
+            let normalized = config.normalize.map(|normalization| {
+                let total: f64 = results
+                    .games
+                    .iter()
+                    .filter_map(|g| g.points)
+                    .map(|points| {
+                        let scores = normalization.apply(points);
+                        seats.iter().map(|&s| scores[s]).sum::<f64>()
+                    })
+                    .sum();
+                total / seat_games
+            });
+            PlayerRow {
+                average: f64::from(seat_results().map(|r| r.total_points).sum::<u32>())
+                    / seat_games,
+                interval: bootstrap::score_interval(results, &seats),
+                win_rate: f64::from(wins) * 100. / f64::from(ok_games),
+                top_rate: f64::from(u32::try_from(top).unwrap_or(u32::MAX)) * 100.
+                    / f64::from(ok_games),
+                name,
+                seats,
+                normalized,
+                metrics,
+            }
+        })
+        .collect()
+}
+
+/// A line per player for scripts, in the order of the summary, with its name, average score and
+/// win rate separated by tabs, and a last `crashed` line with the number of crashed games
+pub fn write_summary_lines(
+    out: &mut impl Write,
+    config: &TestConfig,
+    results: &TestResults,
+    metric_names: &[String],
+) -> io::Result<()> {
+    for row in sorted_rows(config, results, metric_names) {
+        writeln!(out, "{}\t{:.1}\t{:.1}", row.name, row.average, row.win_rate)?;
+    }
+    // The crashes are of whole games, not of a player
+    writeln!(out, "crashed\t{}", results.failed_seeds.len())
+}
+
+fn write_players(
+    out: &mut impl Write,
+    config: &TestConfig,
+    results: &TestResults,
+    metric_names: &[String],
+    color: bool,
+) -> io::Result<()> {
+    let rows = sorted_rows(config, results, metric_names);
+    let key = |row: &PlayerRow| config.sort_by.key(row);
+    let label = PlayerRow::label;
+    let width = rows
+        .iter()
+        .map(|r| label(r).len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    let metric_widths: Vec<_> = metric_names.iter().map(|n| n.len().max(9)).collect();
+
+    writeln!(out, "Game results:")?;
+    let mut header = vec![format!(
+        "{:>4}  {:<width$} {:>9} {:>17} {:>7} {:>7}",
+        "Rank",
+        "Player",
+        "Avg score",
+        format!("{:.0}% CI", bootstrap::CONFIDENCE * 100.),
+        "WR",
+        format!("Top-{}", config.top)
+    )];
+    if let Some(normalization) = config.normalize {
+        header.push(format!("{:>9}", normalization.header()));
+    }
+    header.extend(
+        metric_names
+            .iter()
+            .zip(&metric_widths)
+            .map(|(name, &w)| format!("{name:>w$}")),
+    );
+    writeln!(out, "{}", header.join(" "))?;
+    for (rank, row) in rows.iter().enumerate() {
+        let mut cells = vec![format!(
+            "{:>4}  {:<width$} {:>9.1} {:>17} {:>6.1}% {:>6.1}%",
+            rank + 1,
+            label(row),
+            row.average,
+            row.interval.map_or_else(
+                || "-".to_owned(),
+                |(low, high)| format!("{low:.1} - {high:.1}")
+            ),
+            row.win_rate,
+            row.top_rate
+        )];
+        if let (Some(normalization), Some(value)) = (config.normalize, row.normalized) {
+            cells.push(format!("{:>9}", normalization.format(value)));
+        }
+        cells.extend(
+            row.metrics
+                .iter()
+                .zip(&metric_widths)
+                .map(|(value, &w)| format!("{value:>w$.2}")),
+        );
+        let line = cells.join(" ");
+        // The best player stands out, unless everyone ties
+        if color && rank == 0 && rows.len() > 1 && key(row) > key(&rows[1]) {
+            writeln!(out, "\x1b[1;32m{line}\x1b[0m")?;
+        } else {
+            writeln!(out, "{line}")?;
+        }
+    }
+
+    write_details(out, results, &rows)?;
+    // A player in every seat, like with --mirror, has no one to win by a margin against
+    if rows.len() > 1 {
+        write_margins(out, results, &rows)?;
+    }
+    writeln!(out)?;
+
+    Ok(())
+}
+
+/// Details of the players that don't fit in the table, in the same order
+fn write_details(
+    out: &mut impl Write,
+    results: &TestResults,
+    rows: &[PlayerRow],
+) -> io::Result<()> {
+    let ok_games = f64::from(results.ok_games());
+    let mut details = Vec::new();
+    for row in rows {
+        if row.seats.len() > 1 {
+            for &seat in &row.seats {
+                let res = &results.player_results[seat];
+                details.push(format!(
+                    "{}, seat {}: {:.1} points in average ({:.1}% WR)",
+                    row.name,
+                    seat + 1,
+                    f64::from(res.total_points) / ok_games,
+                    f64::from(res.total_wins) * 100. / ok_games,
+                ));
+            }
+        }
+
+        let seat_games = ok_games * row.seat_count();
+        let seat_results = || row.seats.iter().map(|&seat| &results.player_results[seat]);
+        let sum = |stat: fn(&PlayerResults) -> u32| seat_results().map(stat).sum::<u32>();
+        let (timeouts, disqualifications) = (sum(|r| r.timeouts), sum(|r| r.disqualifications));
+        if timeouts > 0 || disqualifications > 0 {
+            details.push(format!(
+                "{}: timed out in {timeouts} games ({:.1}%), disqualified in {disqualifications} games ({:.1}%)",
+                row.name,
+                f64::from(timeouts) * 100. / seat_games,
+                f64::from(disqualifications) * 100. / seat_games,
+            ));
+        }
+        let (warnings, warned_games) = (sum(|r| r.warnings), sum(|r| r.warned_games));
+        if warnings > 0 {
+            details.push(format!(
+                "{}: {warnings} warnings, {:.1} per game, in {warned_games} games ({:.1}%)",
+                row.name,
+                f64::from(warnings) / seat_games,
+                f64::from(warned_games) * 100. / seat_games,
+            ));
+        }
+        if results.timeline_games > 0 {
+            let timeline_games = f64::from(results.timeline_games) * row.seat_count();
+            let [early, mid, late] = [0, 1, 2].map(|phase| {
+                f64::from(seat_results().map(|r| r.phase_points[phase]).sum::<u32>())
+                    / timeline_games
+            });
+            details.push(format!(
+                "{}: score trajectory of {early:.1} early, {mid:.1} mid, {late:.1} late game",
+                row.name
+            ));
+        }
+    }
+    if !details.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "{}", details.join("\n"))?;
+    }
+    Ok(())
+}
+
+/// The table of the players as Markdown, for places like CI job summaries
+pub fn write_markdown(
+    out: &mut impl Write,
+    config: &TestConfig,
+    results: &TestResults,
+    metric_names: &[String],
+) -> io::Result<()> {
+    let mut header = vec![
+        "Rank".to_owned(),
+        "Player".to_owned(),
+        "Avg score".to_owned(),
+        format!("{:.0}% CI", bootstrap::CONFIDENCE * 100.),
+        "WR".to_owned(),
+        format!("Top-{}", config.top),
+    ];
+    if let Some(normalization) = config.normalize {
+        header.push(normalization.header().to_owned());
+    }
+    header.extend(metric_names.iter().cloned());
+
+    let mut lines = vec![
+        format!("| {} |", header.join(" | ")),
+        // Numbers are right-aligned, names left-aligned
+        format!("|---:|---|{}", "---:|".repeat(header.len() - 2)),
+    ];
+    for (rank, row) in sorted_rows(config, results, metric_names)
+        .iter()
+        .enumerate()
+    {
+        let mut cells = vec![
+            (rank + 1).to_string(),
+            row.label(),
+            format!("{:.1}", row.average),
+            row.interval.map_or_else(
+                || "-".to_owned(),
+                |(low, high)| format!("{low:.1} - {high:.1}"),
+            ),
+            format!("{:.1}%", row.win_rate),
+            format!("{:.1}%", row.top_rate),
+        ];
+        if let (Some(normalization), Some(value)) = (config.normalize, row.normalized) {
+            cells.push(normalization.format(value));
+        }
+        cells.extend(row.metrics.iter().map(|value| format!("{value:.2}")));
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+    writeln!(out, "{}", lines.join("\n"))
+}
+
+/// How far ahead of the runner-up every player finished when it won, in the order of the table
+fn write_margins(
+    out: &mut impl Write,
+    results: &TestResults,
+    rows: &[PlayerRow],
+) -> io::Result<()> {
+    let lines: Vec<_> = rows
+        .iter()
+        .map(|row| match MarginStats::compute(results, &row.seats) {
+            Some(m) => format!(
+                "=> {}: won {} games by {:.1} points in average (min {}, p25 {}, median {}, p75 {}, max {})",
+                row.name, m.wins, m.average, m.min, m.p25, m.median, m.p75, m.max
+            ),
+            None => format!("=> {}: no wins", row.name),
+        })
+        .collect();
+    writeln!(out)?;
+    writeln!(out, "Winning margins:")?;
+    writeln!(out, "{}", lines.join("\n"))
+}
+
+fn write_durations(
+    out: &mut impl Write,
+    stats: &DurationStats,
+    cpu: Option<CpuStats>,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "Game duration: {:.2?} min, {:.2?} in average, {:.2?} p95, {:.2?} max",
+        stats.min, stats.average, stats.p95, stats.max
+    )?;
+    if let Some(cpu) = cpu {
+        writeln!(
+            out,
+            "CPU time: {:.2?} total ({:.2?} user, {:.2?} system), {:.2?} per game in average",
+            cpu.total.total(),
+            cpu.total.user,
+            cpu.total.system,
+            cpu.average
+        )?;
+    }
+    if !stats.slow_seeds.is_empty() {
+        writeln!(out, "Anomalously slow seeds:")?;
+        for (seed, duration) in stats.slow_seeds.iter().take(10) {
+            writeln!(out, "=> {seed}: {duration:.2?}")?;
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_cpu_budget(
+    out: &mut impl Write,
+    budget: &CpuBudget,
+    results: &TestResults,
+) -> io::Result<()> {
+    let over = budget.over(results);
+    writeln!(
+        out,
+        "CPU budget: {:.2?} per game on the judge, {:.2?} here, games over it: {}",
+        budget.budget,
+        budget.local(),
+        over.len()
+    )?;
+    if !over.is_empty() {
+        writeln!(
+            out,
+            "The judge could disqualify the players in these seeds, with their CPU time there:"
+        )?;
+        for (seed, cpu) in over.iter().take(10) {
+            writeln!(out, "=> {seed}: {cpu:.2?}")?;
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Formats a byte count in MiB, the unit memory limits are usually given in
+#[allow(clippy::cast_precision_loss)] // Correctness: Only used for display
+pub fn mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / f64::from(1 << 20))
+}
+
+fn write_memory(out: &mut impl Write, config: &TestConfig, stats: &MemoryStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "Peak memory for {}: {} min, {} in average, {} max",
+        config.players.map(|p| p.as_string()).join(", "),
+        mib(stats.min),
+        mib(stats.average),
+        mib(stats.max)
+    )?;
+    if !stats.heavy_seeds.is_empty() {
+        writeln!(out, "Seeds with anomalously high memory use:")?;
+        for &(seed, rss) in stats.heavy_seeds.iter().take(10) {
+            writeln!(out, "=> {seed}: {}", mib(rss))?;
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_focus(
+    out: &mut impl Write,
+    config: &TestConfig,
+    results: &TestResults,
+    seat: usize,
+) -> io::Result<()> {
+    writeln!(out, "Worst seeds for {}:", config.players[seat].as_string())?;
+    for worst in focus::worst_seeds(results, seat, config.focus_count) {
+        writeln!(
+            out,
+            "=> {}: {} points ({:+.1} vs average){}",
+            worst.seed,
+            worst.points,
+            -worst.deficit,
+            if worst.lost { ", lost" } else { "" }
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_teams(
+    out: &mut impl Write,
+    config: &TestConfig,
+    results: &TestResults,
+    teams: Teams,
+) -> io::Result<()> {
+    writeln!(out, "Team results:")?;
+    let ok_games = f64::from(results.ok_games().max(1));
+    for team in teams.results(results) {
+        let players: Vec<_> = team
+            .seats
+            .iter()
+            .map(|&s| config.players[s].as_string())
+            .collect();
+        writeln!(
+            out,
+            "=> Team {} got {:.1} points in average ({:.1}% WR)",
+            players.join(" + "),
+            team.average,
+            f64::from(team.wins) * 100. / ok_games
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// How the scores of a player against itself vary, which should be the same from every seat
+fn write_mirror(
+    out: &mut impl Write,
+    config: &TestConfig,
+    results: &TestResults,
+) -> io::Result<()> {
+    let scores: Vec<[f64; 4]> = results
+        .games
+        .iter()
+        .filter_map(|g| g.points)
+        .map(|points| points.map(f64::from))
+        .collect();
+    #[allow(clippy::cast_precision_loss)] // Correctness: Seed counts are far below 2^52
+    let (games, total) = (
+        scores.len().max(1) as f64,
+        results.games.len().max(1) as f64,
+    );
+    let mean = |seat: usize| scores.iter().map(|s| s[seat]).sum::<f64>() / games;
+    let std_dev = |seat: usize| {
+        let mean = mean(seat);
+        let variance = scores.iter().map(|s| (s[seat] - mean).powi(2)).sum::<f64>() / games;
+        variance.sqrt()
+    };
+
+    writeln!(out, "Mirror match of {}:", config.players[0].as_string())?;
+    for seat in 0..4 {
+        writeln!(
+            out,
+            "=> Seat {}: {:.1} ± {:.1} points",
+            seat + 1,
+            mean(seat),
+            std_dev(seat)
+        )?;
+    }
+    let means = [0, 1, 2, 3].map(mean);
+    let (min, max) = (
+        means.iter().copied().fold(f64::INFINITY, f64::min),
+        means.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    );
+    let overall = means.iter().sum::<f64>() / 4.;
+    writeln!(
+        out,
+        "   Seat averages differ by {:.1} points ({:.1}% of the average score)",
+        max - min,
+        (max - min) * 100. / overall.max(1.)
+    )?;
+    #[allow(clippy::cast_precision_loss)] // Correctness: Seed counts are far below 2^52
+    let crashes = results.failed_seeds.len() as f64;
+    writeln!(
+        out,
+        "   Crash rate: {:.1}% ({} of {} games)",
+        crashes * 100. / total,
+        results.failed_seeds.len(),
+        results.games.len()
+    )?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_flaky(out: &mut impl Write, config: &TestConfig, results: &TestResults) -> io::Result<()> {
+    let flaky = flaky::find(results);
+    if flaky.is_empty() {
+        writeln!(
+            out,
+            "Every seed had the same outcome in all its repetitions"
+        )?;
+    } else {
+        writeln!(
+            out,
+            "Some seeds had different outcomes between repetitions:"
+        )?;
+    }
+    for seed in flaky {
+        let outcomes: Vec<_> = seed
+            .outcomes
+            .iter()
+            .map(|(outcome, count)| match outcome {
+                Some(winners) => {
+                    let names: Vec<_> = (0..4)
+                        .filter(|&i| winners[i])
+                        .map(|i| config.players[i].as_string())
+                        .collect();
+                    format!("won by {} {count}x", names.join("+"))
+                }
+                None => format!("crashed {count}x"),
+            })
+            .collect();
+        writeln!(out, "=> {}: {}", seed.seed, outcomes.join(", "))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
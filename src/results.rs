@@ -0,0 +1,279 @@
+use crate::parser::{PlayerStatus, PHASES};
+use crate::runner::{CrashKind, ExecutionResults};
+use crate::usage::ResourceUsage;
+
+use itertools::Itertools;
+use std::{collections::BTreeMap, mem};
+
+#[derive(Default)]
+pub struct PlayerResults {
+    pub total_points: u32,
+    pub total_wins: u32,
+    pub timeouts: u32,
+    pub disqualifications: u32,
+    pub warnings: u32,
+    /// Games with at least one warning
+    pub warned_games: u32,
+    pub phase_points: [u32; PHASES],
+    pub metrics: Vec<f64>,
+}
+
+/// Outcome of a single seed, kept to report per-seed results
+pub struct GameRecord {
+    pub seed: u32,
+    /// Final scores, or `None` if the game crashed
+    pub points: Option<[u32; 4]>,
+    /// Timeouts and disqualifications of every seat, all alive if the game crashed
+    pub status: [PlayerStatus; 4],
+    /// Warnings logged about every seat
+    pub warnings: [u32; 4],
+    pub usage: ResourceUsage,
+    /// What ended the game, if it crashed
+    pub crash_kind: Option<CrashKind>,
+    /// How the game crashed, if it did
+    pub signature: Option<String>,
+}
+
+impl GameRecord {
+    /// Points between the winner and the runner-up, if the game didn't crash
+    pub fn margin(&self) -> Option<u32> {
+        let mut points = self.points?;
+        points.sort_unstable();
+        Some(points[3] - points[2])
+    }
+
+    /// Seats with the highest score, if the game didn't crash
+    pub fn winners(&self) -> Option<[bool; 4]> {
+        let points = self.points?;
+        let max = points.iter().max().copied().unwrap_or_default();
+        Some(points.map(|p| p == max))
+    }
+}
+
+/// Cheap running totals, for reporting while the run is still going
+#[derive(Default)]
+pub struct LiveStats {
+    /// Games that didn't crash
+    pub games: u32,
+    pub crashes: u32,
+    pub points: [u64; 4],
+    pub wins: [u32; 4],
+}
+
+impl LiveStats {
+    pub fn record(&mut self, result: &ExecutionResults) {
+        match result {
+            ExecutionResults::Ok { points, .. } => {
+                self.games += 1;
+                let max = points.iter().max().copied().unwrap_or_default();
+                for (i, &p) in points.iter().enumerate() {
+                    self.points[i] += u64::from(p);
+                    if p == max {
+                        self.wins[i] += 1;
+                    }
+                }
+            }
+            ExecutionResults::Crash { .. } => self.crashes += 1,
+            ExecutionResults::ParseError { .. } | ExecutionResults::Unstarted { .. } => {}
+        }
+    }
+}
+
+/// Merges the results of the games in seed order, whatever order they finish in, so the results
+/// (and every list of seeds taken from them) are the same on every run. Only the seeds finishing
+/// ahead of an unfinished one are kept apart
+pub struct OrderedResults {
+    /// First seed that isn't merged yet
+    next: u32,
+    /// Games of every seed
+    repeat: usize,
+    pending: BTreeMap<u32, Vec<TestResults>>,
+    merged: TestResults,
+}
+
+impl OrderedResults {
+    pub fn new(first_seed: u32, repeat: u32) -> Self {
+        Self {
+            next: first_seed,
+            repeat: repeat as usize,
+            pending: BTreeMap::new(),
+            merged: TestResults::default(),
+        }
+    }
+
+    /// First seed with games still running
+    pub fn next(&self) -> u32 {
+        self.next
+    }
+
+    pub fn push(&mut self, seed: u32, results: TestResults) {
+        self.pending.entry(seed).or_default().push(results);
+        while self
+            .pending
+            .get(&self.next)
+            .is_some_and(|r| r.len() == self.repeat)
+        {
+            for results in self.pending.remove(&self.next).unwrap_or_default() {
+                self.merged = mem::take(&mut self.merged).merge(results);
+            }
+            self.next = self.next.wrapping_add(1);
+        }
+    }
+
+    /// Merged results, including any seed still missing some of its games
+    pub fn finish(mut self) -> TestResults {
+        for results in mem::take(&mut self.pending).into_values().flatten() {
+            self.merged = mem::take(&mut self.merged).merge(results);
+        }
+        self.merged
+    }
+}
+
+#[derive(Default)]
+pub struct TestResults {
+    pub player_results: [PlayerResults; 4],
+    pub failed_seeds: Vec<u32>,
+    /// Seeds whose game couldn't be started, left out of everything else
+    pub unstarted_seeds: Vec<u32>,
+    /// Seeds where the score regex didn't find every score, also left out of everything else
+    pub parse_error_seeds: Vec<u32>,
+    pub timeline_games: u32,
+    pub games: Vec<GameRecord>,
+}
+
+impl From<ExecutionResults> for TestResults {
+    fn from(value: ExecutionResults) -> Self {
+        let mut ret = TestResults::default();
+        match value {
+            ExecutionResults::Ok {
+                seed,
+                points,
+                status,
+                warnings,
+                phases,
+                metrics,
+                usage,
+            } => {
+                for i in 0..4 {
+                    ret.player_results[i].total_points = points[i];
+                    if points[i] == *points.iter().max().unwrap() {
+                        ret.player_results[i].total_wins = 1;
+                    }
+                    ret.player_results[i].warnings = warnings[i];
+                    ret.player_results[i].warned_games = u32::from(warnings[i] > 0);
+                    match status[i] {
+                        PlayerStatus::Alive => {}
+                        PlayerStatus::TimedOut => ret.player_results[i].timeouts = 1,
+                        PlayerStatus::Disqualified => ret.player_results[i].disqualifications = 1,
+                    }
+                }
+                for (res, i) in ret.player_results.iter_mut().zip(0..) {
+                    res.metrics = metrics.iter().map(|m| m[i]).collect();
+                }
+                if let Some(phases) = phases {
+                    ret.timeline_games = 1;
+                    for (phase, scores) in phases.iter().enumerate() {
+                        for (res, &points) in ret.player_results.iter_mut().zip(scores) {
+                            res.phase_points[phase] = points;
+                        }
+                    }
+                }
+                ret.games = vec![GameRecord {
+                    seed,
+                    points: Some(points),
+                    status,
+                    warnings,
+                    usage,
+                    crash_kind: None,
+                    signature: None,
+                }];
+            }
+            ExecutionResults::Crash {
+                seed,
+                usage,
+                kind,
+                signature,
+            } => {
+                ret.failed_seeds = vec![seed];
+                ret.games = vec![GameRecord {
+                    seed,
+                    points: None,
+                    status: [PlayerStatus::Alive; 4],
+                    warnings: [0; 4],
+                    usage,
+                    crash_kind: Some(kind),
+                    signature: Some(signature),
+                }];
+            }
+            ExecutionResults::ParseError { seed, .. } => ret.parse_error_seeds = vec![seed],
+            ExecutionResults::Unstarted { seed } => ret.unstarted_seeds = vec![seed],
+        }
+        ret
+    }
+}
+
+impl TestResults {
+    /// Number of games that didn't crash
+    pub fn ok_games(&self) -> u32 {
+        #[allow(clippy::cast_possible_truncation)]
+        // Correctness: We can't run more than u32::MAX seeds
+        let ret = (self.games.len() - self.failed_seeds.len()) as u32;
+        ret
+    }
+
+    /// How many games crashed of every kind, the most common first
+    pub fn crash_kinds(&self) -> Vec<(CrashKind, usize)> {
+        self.games
+            .iter()
+            .filter_map(|g| g.crash_kind)
+            .counts()
+            .into_iter()
+            .sorted_by_key(|&(kind, count)| (std::cmp::Reverse(count), kind))
+            .collect()
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        self.failed_seeds.extend_from_slice(&other.failed_seeds);
+        self.unstarted_seeds
+            .extend_from_slice(&other.unstarted_seeds);
+        self.parse_error_seeds
+            .extend_from_slice(&other.parse_error_seeds);
+        self.timeline_games += other.timeline_games;
+        self.games.extend(other.games);
+        for (a, b) in self.player_results.iter_mut().zip(other.player_results) {
+            a.total_points += b.total_points;
+            a.total_wins += b.total_wins;
+            a.timeouts += b.timeouts;
+            a.disqualifications += b.disqualifications;
+            a.warnings += b.warnings;
+            a.warned_games += b.warned_games;
+            for (a, b) in a.phase_points.iter_mut().zip(b.phase_points) {
+                *a += b;
+            }
+            if a.metrics.len() < b.metrics.len() {
+                a.metrics.resize(b.metrics.len(), 0.);
+            }
+            for (a, b) in a.metrics.iter_mut().zip(b.metrics) {
+                *a += b;
+            }
+        }
+        self
+    }
+
+    /// Per-seed results as CSV, sorted by seed
+    pub fn seeds_csv(&self, players: &[String; 4]) -> String {
+        let mut games: Vec<_> = self.games.iter().collect();
+        games.sort_by_key(|g| g.seed);
+
+        let mut lines = vec![format!("seed,status,{}", players.join(","))];
+        lines.extend(games.iter().map(|game| match game.points {
+            Some(points) => format!(
+                "{},ok,{}",
+                game.seed,
+                points.map(|p| p.to_string()).join(",")
+            ),
+            None => format!("{},crash,,,,", game.seed),
+        }));
+        lines.join("\n") + "\n"
+    }
+}
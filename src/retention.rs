@@ -0,0 +1,205 @@
+//! What's left of the replays and crash dumps of a run once it's over, so runs of tens of
+//! thousands of games don't take gigabytes.
+
+use crate::errors::AppError;
+use crate::interesting;
+use crate::report;
+use crate::results::TestResults;
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Files compressed by a single run of the compressor
+const CHUNK: usize = 256;
+
+/// Replays kept after the run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Retain {
+    /// Every replay
+    #[default]
+    All,
+    /// Only the replays of the games that crashed or whose scores weren't found
+    Crashes,
+    /// The replays of the crashes and of the --retain-count most interesting games
+    Interesting,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn command(self) -> Command {
+        match self {
+            Self::Gzip => {
+                let mut command = Command::new("gzip");
+                command.args(["-f", "-q", "--"]);
+                command
+            }
+            Self::Zstd => {
+                let mut command = Command::new("zstd");
+                command.args(["-f", "-q", "--rm", "--"]);
+                command
+            }
+        }
+    }
+
+    /// Checks the compressor can be run, before the run rather than once it's over
+    pub fn check(self) -> Result<(), AppError> {
+        let mut command = Command::new(self.command().get_program());
+        let program = command.get_program().to_string_lossy().into_owned();
+        match command.arg("--version").output() {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => Err(AppError::CompressionFailed(format!(
+                "{program} --version {}",
+                output.status
+            ))),
+            Err(err) => Err(AppError::CompressionFailed(format!("{program}: {err}"))),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// Compresses the files in place with the compressor's command, returning the paths of the
+    /// compressed files
+    fn compress(self, files: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+        files
+            .par_chunks(CHUNK)
+            .try_for_each(|chunk| -> Result<()> {
+                let mut command = self.command();
+                let program = command.get_program().to_string_lossy().into_owned();
+                let status = command
+                    .args(chunk)
+                    .status()
+                    .map_err(|err| AppError::CompressionFailed(format!("{program}: {err}")))?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(AppError::CompressionFailed(format!("{program} {status}")).into())
+                }
+            })?;
+        Ok(files
+            .into_iter()
+            .map(|file| {
+                let mut name = file.into_os_string();
+                name.push(".");
+                name.push(self.extension());
+                PathBuf::from(name)
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Retention {
+    pub retain: Retain,
+    /// Interesting games whose replays are kept
+    pub count: usize,
+    /// Bytes the replays and crash dumps of the run can take, removing the least interesting
+    /// ones first
+    pub max_bytes: Option<u64>,
+    pub compress: Option<Compression>,
+}
+
+impl Retention {
+    pub fn is_enabled(&self) -> bool {
+        self.retain != Retain::All || self.max_bytes.is_some() || self.compress.is_some()
+    }
+
+    /// Thins out and compresses the replays in `replays` and the crash dumps in `crash_dumps`,
+    /// writing what's left to `out`. Only the files of the seeds of the run are touched, the
+    /// folders may hold older ones
+    pub fn apply(
+        &self,
+        out: &mut impl Write,
+        replays: Option<&Path>,
+        crash_dumps: Option<&Path>,
+        results: &TestResults,
+        focus: usize,
+    ) -> Result<()> {
+        // Games whose scores weren't found are kept like crashes, they're as worth looking into
+        let crashed: Vec<u32> = results
+            .failed_seeds
+            .iter()
+            .chain(&results.parse_error_seeds)
+            .copied()
+            .sorted()
+            .dedup()
+            .collect();
+        let mut removed = 0;
+
+        // From the most to the least worth keeping, which is the order the size limit follows
+        let mut files = Vec::new();
+        if let Some(dir) = crash_dumps {
+            files.extend(
+                crashed
+                    .iter()
+                    .map(|seed| dir.join(format!("{seed}.stderr"))),
+            );
+        }
+        if let Some(dir) = replays {
+            // A seed played several times may be in both lists
+            let seeds: Vec<u32> = crashed
+                .iter()
+                .copied()
+                .chain(interesting::ranked(results, focus))
+                .unique()
+                .collect();
+            let keep = match self.retain {
+                Retain::All => seeds.len(),
+                Retain::Crashes => crashed.len(),
+                Retain::Interesting => crashed.len() + self.count,
+            };
+            for (i, seed) in seeds.iter().enumerate() {
+                let path = dir.join(format!("{seed}.res"));
+                if i < keep {
+                    files.push(path);
+                } else if path.exists() {
+                    fs::remove_file(path)?;
+                    removed += 1;
+                }
+            }
+        }
+        files.retain(|file| file.exists());
+
+        if let Some(compression) = self.compress {
+            files = compression.compress(files)?;
+        }
+
+        let size = |file: &PathBuf| fs::metadata(file).map_or(0, |m| m.len());
+        let mut total: u64 = files.iter().map(size).sum();
+        if let Some(max) = self.max_bytes {
+            while total > max {
+                let Some(file) = files.pop() else {
+                    break;
+                };
+                total -= size(&file);
+                fs::remove_file(file)?;
+                removed += 1;
+            }
+        }
+
+        writeln!(
+            out,
+            "Kept {} replays and crash dumps ({}), removed {removed}",
+            files.len(),
+            report::mib(total)
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,825 @@
+use crate::cpu_budget::CpuBudget;
+use crate::errors::AppError;
+use crate::parser::{
+    MetricParser, PlayerStatus, ScoreParser, StatusParser, TimelineParser, WarningParser, PHASES,
+};
+use crate::remote::Workers;
+use crate::sandbox::Sandbox;
+use crate::sweep;
+use crate::usage::{self, ResourceUsage};
+use crate::workdir::WorkDir;
+use crate::TestConfig;
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use regex::RegexSet;
+use std::{
+    fmt::{self, Write as _},
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{self, Path, PathBuf},
+    process::{self, Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, warn};
+
+/// Game executable used unless `--game` says otherwise
+pub const DEFAULT_GAME: &str = if cfg!(windows) { "Game.exe" } else { "./Game" };
+
+/// Bytes of game output kept besides the lines the parsers look for, unless `--stderr-limit`
+/// says otherwise
+pub const DEFAULT_STDERR_LIMIT: usize = 1 << 20;
+
+/// Times starting a game is retried after a transient error, unless `--spawn-retries` says
+/// otherwise
+pub const DEFAULT_SPAWN_RETRIES: u32 = 3;
+
+/// How the game gets its settings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SettingsVia {
+    /// Written to its stdin once it starts
+    #[default]
+    Stdin,
+    /// In a file given on its command line, as `-i FILE`
+    Arg,
+    /// In a file its stdin is redirected from, like `Game ... < FILE`
+    Tempfile,
+}
+
+impl SettingsVia {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stdin => "stdin",
+            Self::Arg => "arg",
+            Self::Tempfile => "tempfile",
+        }
+    }
+}
+
+/// Settings of the games, along with the file holding them for the modes that need one. The file
+/// is removed along with the settings
+pub struct Settings {
+    text: String,
+    via: SettingsVia,
+    file: Option<PathBuf>,
+}
+
+impl Settings {
+    pub fn new(text: String, via: SettingsVia) -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let file = if via == SettingsVia::Stdin {
+            None
+        } else {
+            // In the current directory, which the sandboxes can see unlike their private /tmp
+            let n = COUNT.fetch_add(1, Ordering::Relaxed);
+            let path = path::absolute(format!(".eda-game-tester-{}-{n}.cnf", process::id()))?;
+            fs::write(&path, &text)?;
+            Some(path)
+        };
+        Ok(Self { text, via, file })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn via(&self) -> SettingsVia {
+        self.via
+    }
+
+    /// Arguments the game needs to find its settings
+    pub fn args(&self) -> Vec<String> {
+        match (self.via, &self.file) {
+            (SettingsVia::Arg, Some(file)) => {
+                vec!["-i".to_owned(), file.to_string_lossy().into_owned()]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Stdin of the game, which gets the settings written to it if it's piped
+    fn stdin(&self) -> io::Result<Stdio> {
+        match (self.via, &self.file) {
+            (SettingsVia::Tempfile, Some(file)) => File::open(file).map(Stdio::from),
+            (SettingsVia::Arg, _) => Ok(Stdio::null()),
+            _ => Ok(Stdio::piped()),
+        }
+    }
+}
+
+impl Drop for Settings {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            if let Err(err) = fs::remove_file(file) {
+                warn!(path = %file.display(), %err, "Couldn't remove the settings file");
+            }
+        }
+    }
+}
+
+/// Wait before the first retry, doubled on every following one
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Whether `err` comes from the system running short of something, like processes or file
+/// descriptors under high parallelism, so trying again later may work
+fn is_transient(err: &color_eyre::Report) -> bool {
+    let Some(err) = err.downcast_ref::<io::Error>() else {
+        return false;
+    };
+    #[cfg(unix)]
+    if let Some(code) = err.raw_os_error() {
+        return [
+            libc::EAGAIN,
+            libc::ENOMEM,
+            libc::EMFILE,
+            libc::ENFILE,
+            libc::EINTR,
+        ]
+        .contains(&code);
+    }
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted | io::ErrorKind::OutOfMemory
+    )
+}
+
+/// Longest line kept, the rest of a longer one is dropped so a line that never ends doesn't fill
+/// the memory
+const MAX_LINE: u64 = 64 << 10;
+
+/// Drops the rest of the line `reader` is in the middle of, copying it to our stderr with `echo`
+fn skip_line(reader: &mut impl BufRead, echo: bool) -> io::Result<()> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let end = buf.iter().position(|&b| b == b'\n');
+        let len = end.map_or(buf.len(), |i| i + 1);
+        if echo {
+            io::stderr().write_all(&buf[..len])?;
+        }
+        reader.consume(len);
+        if end.is_some() {
+            return Ok(());
+        }
+    }
+}
+
+/// Which lines of the game's stderr are kept, as it's read. The lines matching the patterns of
+/// the parsers are always kept, while the rest are only kept up to a limit, so the games of an AI
+/// full of debug prints don't use up the memory
+#[derive(Clone)]
+pub struct Capture {
+    patterns: Vec<String>,
+    set: RegexSet,
+    limit: usize,
+    /// Also copy every line to our stderr as it's read
+    echo: bool,
+}
+
+impl Capture {
+    pub fn new(patterns: Vec<String>, limit: usize) -> Result<Self, AppError> {
+        Ok(Self {
+            set: RegexSet::new(&patterns)?,
+            patterns,
+            limit,
+            echo: false,
+        })
+    }
+
+    /// Copies the whole output to our stderr as the game runs, on top of keeping the lines
+    pub fn echoing(self) -> Self {
+        Self { echo: true, ..self }
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Reads `output` line by line until it's closed, returning the kept lines
+    fn read(&self, output: impl Read) -> io::Result<String> {
+        let mut reader = BufReader::new(output);
+        let mut kept = String::new();
+        let mut other = 0;
+        let mut dropped = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if (&mut reader).take(MAX_LINE).read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if self.echo {
+                io::stderr().write_all(&line)?;
+            }
+            if line.last() != Some(&b'\n') {
+                skip_line(&mut reader, self.echo)?;
+                line.push(b'\n');
+            }
+            let line = String::from_utf8_lossy(&line);
+            if self.set.is_match(&line) {
+                kept.push_str(&line);
+            } else if other + line.len() <= self.limit {
+                other += line.len();
+                kept.push_str(&line);
+            } else {
+                dropped += line.len();
+            }
+        }
+        if dropped > 0 {
+            let _ = writeln!(kept, "[{dropped} more bytes of output over --stderr-limit]");
+        }
+        Ok(kept)
+    }
+}
+
+pub enum ExecutionResults {
+    Ok {
+        seed: u32,
+        points: [u32; 4],
+        status: [PlayerStatus; 4],
+        /// Warnings the game logged about every seat
+        warnings: [u32; 4],
+        phases: Option<[[u32; 4]; PHASES]>,
+        metrics: Vec<[f64; 4]>,
+        usage: ResourceUsage,
+    },
+    Crash {
+        seed: u32,
+        usage: ResourceUsage,
+        kind: CrashKind,
+        /// How the game ended and the last line of its stderr, to tell the causes apart
+        signature: String,
+    },
+    /// The game finished but the score regex didn't find the scores of all the players, like
+    /// when the output format changed, so its scores can't be trusted
+    ParseError {
+        seed: u32,
+        /// Players whose score was found
+        found: usize,
+        usage: ResourceUsage,
+    },
+    /// The game couldn't be started, even after retrying, so it says nothing about the players
+    Unstarted { seed: u32 },
+}
+
+/// What ended a game that didn't exit successfully, to tell the bugs of the players apart from
+/// the engine's and from the machine running out of memory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CrashKind {
+    /// `SIGSEGV`, usually a player reading memory it shouldn't
+    Segfault,
+    /// `SIGABRT`, usually an assertion of the engine
+    Abort,
+    /// Killed by the tester for running longer than `--game-timeout`
+    Timeout,
+    /// `SIGXCPU`, or `SIGKILL` if it was ignored, from going over the CPU time limit of
+    /// `--enforce-cpu-budget`
+    CpuBudget,
+    /// `SIGKILL` the tester didn't send, usually the OOM killer
+    Killed,
+    /// Any other signal
+    Signal(i32),
+    /// A nonzero exit code
+    Exit(i32),
+    /// Crashed on a worker that didn't tell how
+    Unknown,
+}
+
+impl CrashKind {
+    /// How a game with `status` ended, `timed_out` if the tester killed it for its timeout and
+    /// `over_budget` if it used all the CPU time of an enforced --cpu-budget, which kills the games
+    /// that ignore `SIGXCPU`
+    pub fn of(status: ExitStatus, timed_out: bool, over_budget: bool) -> Self {
+        #[cfg(unix)]
+        if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+            return match signal {
+                libc::SIGKILL if timed_out => Self::Timeout,
+                libc::SIGKILL if over_budget => Self::CpuBudget,
+                libc::SIGKILL => Self::Killed,
+                libc::SIGSEGV => Self::Segfault,
+                libc::SIGABRT => Self::Abort,
+                libc::SIGXCPU => Self::CpuBudget,
+                signal => Self::Signal(signal),
+            };
+        }
+        match status.code() {
+            _ if timed_out => Self::Timeout,
+            Some(code) => Self::Exit(code),
+            None => Self::Unknown,
+        }
+    }
+
+    /// Short name without spaces, for the worker protocol and the exports
+    pub fn id(self) -> String {
+        match self {
+            Self::Segfault => "segfault".to_owned(),
+            Self::Abort => "abort".to_owned(),
+            Self::Timeout => "timeout".to_owned(),
+            Self::CpuBudget => "cpu-budget".to_owned(),
+            Self::Killed => "killed".to_owned(),
+            Self::Signal(signal) => format!("signal-{signal}"),
+            Self::Exit(code) => format!("exit-{code}"),
+            Self::Unknown => "unknown".to_owned(),
+        }
+    }
+
+    pub fn from_id(id: &str) -> Self {
+        let number = |prefix| id.strip_prefix(prefix).and_then(|n| n.parse().ok());
+        match id {
+            "segfault" => Self::Segfault,
+            "abort" => Self::Abort,
+            "timeout" => Self::Timeout,
+            "cpu-budget" => Self::CpuBudget,
+            "killed" => Self::Killed,
+            _ => number("signal-")
+                .map(Self::Signal)
+                .or_else(|| number("exit-").map(Self::Exit))
+                .unwrap_or(Self::Unknown),
+        }
+    }
+}
+
+impl fmt::Display for CrashKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Segfault => write!(f, "segmentation fault (SIGSEGV)"),
+            Self::Abort => write!(f, "aborted (SIGABRT)"),
+            Self::Timeout => write!(f, "killed after the --game-timeout"),
+            Self::CpuBudget => write!(f, "killed over the --cpu-budget"),
+            Self::Killed => write!(f, "killed (SIGKILL), likely by the OOM killer"),
+            Self::Signal(signal) => write!(f, "killed by signal {signal}"),
+            Self::Exit(code) => write!(f, "exit code {code}"),
+            Self::Unknown => write!(f, "crashed on a worker"),
+        }
+    }
+}
+
+impl ExecutionResults {
+    pub fn seed(&self) -> u32 {
+        match self {
+            Self::Ok { seed, .. }
+            | Self::Crash { seed, .. }
+            | Self::ParseError { seed, .. }
+            | Self::Unstarted { seed } => *seed,
+        }
+    }
+
+    pub fn usage(&self) -> ResourceUsage {
+        match self {
+            Self::Ok { usage, .. } | Self::Crash { usage, .. } | Self::ParseError { usage, .. } => {
+                *usage
+            }
+            Self::Unstarted { .. } => ResourceUsage::default(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.usage().duration
+    }
+}
+
+impl Default for ExecutionResults {
+    fn default() -> Self {
+        Self::Ok {
+            seed: 0,
+            points: [0; 4],
+            status: [PlayerStatus::Alive; 4],
+            warnings: [0; 4],
+            phases: None,
+            metrics: Vec::new(),
+            usage: ResourceUsage::default(),
+        }
+    }
+}
+
+/// Gets notified of every game as soon as it finishes, while the run is still going
+pub trait GameObserver: Sync {
+    fn game_started(&self, _seed: u32) -> Result<()> {
+        Ok(())
+    }
+
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()>;
+
+    /// Called once every game has finished
+    fn run_finished(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: GameObserver + Send> GameObserver for std::sync::Arc<T> {
+    fn game_started(&self, seed: u32) -> Result<()> {
+        T::game_started(self, seed)
+    }
+
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()> {
+        T::game_finished(self, result)
+    }
+
+    fn run_finished(&self) -> Result<()> {
+        T::run_finished(self)
+    }
+}
+
+/// Everything needed to run a single game and parse its results
+pub struct GameRunner {
+    player_names: [String; 4],
+    settings: Settings,
+    save_output: Option<PathBuf>,
+    crash_dumps: Option<PathBuf>,
+    parser: ScoreParser,
+    status_parser: StatusParser,
+    warning_parser: WarningParser,
+    timeline_parser: Option<TimelineParser>,
+    metric_parser: MetricParser,
+    capture: Capture,
+    game: PathBuf,
+    timeout: Option<Duration>,
+    cpu_budget: Option<CpuBudget>,
+    retries: u32,
+    workers: Option<Workers>,
+    sandbox: Option<Sandbox>,
+    isolate: bool,
+    env: Vec<(String, String)>,
+    fail_fast: bool,
+    /// Set once the run is stopped, killing the games still running
+    cancelled: AtomicBool,
+}
+
+impl GameRunner {
+    pub fn new(
+        config: &TestConfig,
+        save_output: Option<PathBuf>,
+        crash_dumps: Option<PathBuf>,
+    ) -> Result<Self> {
+        let mut f = File::open(&config.settings_file)?;
+        let mut settings = String::new();
+        f.read_to_string(&mut settings)?;
+        if !config.settings.is_empty() {
+            settings = sweep::override_settings(&settings, &config.settings);
+        }
+
+        if !config.workers.is_empty() && config.settings_via != SettingsVia::Stdin {
+            return Err(AppError::SettingsViaWorkers.into());
+        }
+        let settings = Settings::new(settings, config.settings_via)?;
+
+        for dir in [&save_output, &crash_dumps].into_iter().flatten() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut patterns = vec![
+            config.score_regex.clone(),
+            config.timeout_regex.clone(),
+            config.disqualified_regex.clone(),
+            config.warning_regex.clone(),
+        ];
+        if let Some((round, score)) = &config.timeline {
+            patterns.extend([round.clone(), score.clone()]);
+        }
+        patterns.extend(config.metrics.iter().cloned());
+
+        Ok(Self {
+            player_names: config.players.map(|p| p.as_string()),
+            settings,
+            save_output,
+            crash_dumps,
+            parser: ScoreParser::new(&config.score_regex)?,
+            status_parser: StatusParser::new(&config.timeout_regex, &config.disqualified_regex)?,
+            warning_parser: WarningParser::new(&config.warning_regex)?,
+            timeline_parser: config
+                .timeline
+                .as_ref()
+                .map(|(round, score)| TimelineParser::new(round, score))
+                .transpose()?,
+            metric_parser: MetricParser::new(&config.metrics)?,
+            capture: Capture::new(patterns, config.stderr_limit)?,
+            game: config.game.clone(),
+            timeout: config.game_timeout,
+            cpu_budget: config.cpu_budget,
+            retries: config.spawn_retries,
+            workers: (!config.workers.is_empty())
+                .then(|| Workers::setup(&config.workers, &config.game))
+                .transpose()?,
+            sandbox: config.sandbox.clone(),
+            isolate: config.isolate,
+            env: config.env.clone(),
+            fail_fast: config.fail_fast,
+            cancelled: AtomicBool::new(false),
+        })
+    }
+
+    pub fn player_names(&self) -> &[String; 4] {
+        &self.player_names
+    }
+
+    pub fn settings(&self) -> &str {
+        self.settings.text()
+    }
+
+    pub fn settings_via(&self) -> SettingsVia {
+        self.settings.via()
+    }
+
+    pub fn env(&self) -> &[(String, String)] {
+        &self.env
+    }
+
+    /// Shows the game's stderr live, for looking into a single game
+    pub fn with_live_output(mut self) -> Self {
+        self.capture = self.capture.echoing();
+        self
+    }
+
+    pub fn capture(&self) -> &Capture {
+        &self.capture
+    }
+
+    pub fn output_dir(&self) -> Option<&Path> {
+        self.save_output.as_deref()
+    }
+
+    pub fn crash_dump_dir(&self) -> Option<&Path> {
+        self.crash_dumps.as_deref()
+    }
+
+    pub fn metric_names(&self) -> &[String] {
+        self.metric_parser.names()
+    }
+
+    /// Whether the run stops at the first failed game
+    pub fn fail_fast(&self) -> bool {
+        self.fail_fast
+    }
+
+    /// Kills the games still running with `--fail-fast`, and the ones started later
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// What went wrong in a game, if it counts as failed for `--fail-fast`
+    pub fn failure(&self, result: &ExecutionResults) -> Option<String> {
+        match result {
+            ExecutionResults::Ok { status, .. } => {
+                status
+                    .iter()
+                    .zip(&self.player_names)
+                    .find_map(|(status, name)| match status {
+                        PlayerStatus::Alive => None,
+                        PlayerStatus::TimedOut => Some(format!("had {name} time out")),
+                        PlayerStatus::Disqualified => Some(format!("had {name} disqualified")),
+                    })
+            }
+            ExecutionResults::Crash { kind, .. } => Some(format!("crashed: {kind}")),
+            ExecutionResults::ParseError { found, .. } => {
+                Some(format!("only had {found} of the 4 scores found"))
+            }
+            ExecutionResults::Unstarted { .. } => Some("couldn't be started".to_owned()),
+        }
+    }
+
+    pub fn run(&self, seed: u32) -> Result<ExecutionResults> {
+        let output = self
+            .save_output
+            .as_ref()
+            .map(|dir| dir.join(format!("{seed}.res")));
+        self.run_saving(seed, output.as_deref())
+    }
+
+    /// Runs a game, saving its output to the given file instead of the configured folder
+    pub fn run_saving(&self, seed: u32, output: Option<&Path>) -> Result<ExecutionResults> {
+        match self.play_seed(seed, output)? {
+            Some((status, output, usage)) => {
+                let failure = self.describe_failure(status, &usage);
+                self.parse(seed, failure, &output, usage)
+            }
+            None => Ok(ExecutionResults::Unstarted { seed }),
+        }
+    }
+
+    /// How a game ended, if it failed
+    fn describe_failure(&self, status: ExitStatus, usage: &ResourceUsage) -> Option<CrashKind> {
+        let timed_out = self.timeout.is_some_and(|t| usage.duration >= t);
+        let over_budget = self.cpu_budget.is_some_and(|b| b.used_up(usage));
+        (!status.success()).then(|| CrashKind::of(status, timed_out, over_budget))
+    }
+
+    /// Plays a game, retrying if it fails to start for a transient reason. Returns `None` if it
+    /// never started
+    fn play_seed(
+        &self,
+        seed: u32,
+        output: Option<&Path>,
+    ) -> Result<Option<(ExitStatus, String, ResourceUsage)>> {
+        let workdir = self.isolate.then(|| WorkDir::create(seed)).transpose()?;
+        let mut args = self.player_names.to_vec();
+        args.extend(["-s".to_owned(), seed.to_string()]);
+        args.extend(self.settings.args());
+        let (host, mut command) = if let Some(workers) = &self.workers {
+            let (host, command) = workers.command(&args, &self.env, self.timeout);
+            (Some(host), command)
+        } else {
+            let workdir = workdir.as_ref().map(WorkDir::path);
+            let mut command = if let Some(sandbox) = &self.sandbox {
+                sandbox.command(&self.game, &args, workdir)
+            } else {
+                let mut command = Command::new(&self.game);
+                command.args(&args);
+                command
+            };
+            if let Some(workdir) = workdir {
+                command.current_dir(workdir);
+            }
+            if let Some(budget) = self.cpu_budget.filter(|b| b.enforce) {
+                budget.enforce_on(&mut command);
+            }
+            command.envs(self.env.iter().map(|(k, v)| (k, v)));
+            (None, command)
+        };
+        debug!(seed, host, ?command, "Spawning game");
+
+        let mut attempt = 0;
+        let (status, output, mut usage) = loop {
+            let played = output
+                .map_or_else(
+                    || Ok(Stdio::null()),
+                    |path| File::create(path).map(Stdio::from),
+                )
+                .map_err(Into::into)
+                .and_then(|stdout| {
+                    play(
+                        &mut command,
+                        &self.settings,
+                        stdout,
+                        self.timeout,
+                        self.fail_fast.then_some(&self.cancelled),
+                        &self.capture,
+                    )
+                });
+            match played {
+                Ok(played) => break played,
+                Err(err) if is_transient(&err) && attempt < self.retries => {
+                    let backoff = RETRY_BACKOFF * 2u32.pow(attempt);
+                    attempt += 1;
+                    warn!(seed, %err, attempt, ?backoff, "Couldn't start the game, retrying");
+                    thread::sleep(backoff);
+                }
+                Err(err) if is_transient(&err) => {
+                    warn!(seed, %err, "Couldn't start the game, giving up");
+                    return Ok(None);
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        if host.is_some() {
+            // The local child is only ssh, the game's own usage isn't known
+            usage.cpu = None;
+            usage.max_rss = None;
+        }
+        info!(
+            seed,
+            duration_ms = usage.duration.as_millis(),
+            cpu_ms = usage.cpu.map(|c| c.total().as_millis()),
+            max_rss = usage.max_rss,
+            %status,
+            "Game finished"
+        );
+
+        if !status.success() && !self.is_cancelled() {
+            warn!(seed, %status, "Game crashed");
+        }
+        Ok(Some((status, output, usage)))
+    }
+
+    /// Turns the stderr of a game, possibly played somewhere else, into its results. `failure`
+    /// tells how the game ended if it didn't exit successfully
+    pub fn parse(
+        &self,
+        seed: u32,
+        failure: Option<CrashKind>,
+        output: &str,
+        usage: ResourceUsage,
+    ) -> Result<ExecutionResults> {
+        if let Some(kind) = failure {
+            if let Some(dir) = &self.crash_dumps {
+                fs::write(dir.join(format!("{seed}.stderr")), output)?;
+            }
+            return Ok(ExecutionResults::Crash {
+                seed,
+                usage,
+                kind,
+                signature: crash_signature(kind, output),
+            });
+        }
+
+        let (points, found) = self.parser.parse(output, &self.player_names);
+        if found < 4 {
+            warn!(
+                seed,
+                found, "The score regex only matched some of the players"
+            );
+            return Ok(ExecutionResults::ParseError { seed, found, usage });
+        }
+
+        Ok(ExecutionResults::Ok {
+            seed,
+            points,
+            status: self.status_parser.parse(output, &self.player_names),
+            warnings: self.warning_parser.parse(output, &self.player_names),
+            phases: self
+                .timeline_parser
+                .as_ref()
+                .and_then(|p| p.parse(output, &self.player_names)),
+            metrics: self.metric_parser.parse(output, &self.player_names),
+            usage,
+        })
+    }
+}
+
+/// Longest piece of the last output line kept in a crash signature
+const SIGNATURE_LINE: usize = 160;
+
+/// How a game ended along with its last line of output, which usually tells the assertion or
+/// error message that made it crash
+fn crash_signature(kind: CrashKind, output: &str) -> String {
+    let last = output
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty() && !line.ends_with("over --stderr-limit]"));
+    match last {
+        Some(line) => {
+            let line: String = line.chars().take(SIGNATURE_LINE).collect();
+            format!("{kind}: {line}")
+        }
+        None => kind.to_string(),
+    }
+}
+
+/// Plays a game, feeding it the settings and collecting the lines of its stderr that `capture`
+/// keeps. The game is killed if it runs for longer than `timeout`, or once `cancel` is set. The
+/// command needs the arguments of the settings already
+pub fn play(
+    command: &mut Command,
+    settings: &Settings,
+    stdout: Stdio,
+    timeout: Option<Duration>,
+    cancel: Option<&AtomicBool>,
+    capture: &Capture,
+) -> Result<(ExitStatus, String, ResourceUsage)> {
+    #[cfg(unix)]
+    if timeout.is_some() || cancel.is_some() {
+        // So the processes the game starts are killed along with it
+        std::os::unix::process::CommandExt::process_group(command, 0);
+    }
+
+    let start = Instant::now();
+    let mut child = command
+        .stdin(settings.stdin()?)
+        .stdout(stdout)
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Both pipes are served on their own threads, so writing the settings can't deadlock with a
+    // game that fills its stderr before reading them, and the game can be killed at any time
+    let writer = child.stdin.take().map(|mut stdin| {
+        let settings = settings.text().to_owned();
+        thread::spawn(move || stdin.write_all(settings.as_bytes()))
+    });
+
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or(AppError::BrokenChildCommunication)?;
+    let capture = capture.clone();
+    let reader = thread::spawn(move || capture.read(stderr));
+
+    let (status, usage) = usage::wait(&mut child, start, timeout.map(|t| start + t), cancel)?;
+    let output = reader
+        .join()
+        .map_err(|_| AppError::BrokenChildCommunication)??;
+    if let Some(writer) = writer {
+        match writer
+            .join()
+            .map_err(|_| AppError::BrokenChildCommunication)?
+        {
+            // The game can exit before reading all of its settings, which is up to its exit
+            // status
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => return Err(err.into()),
+            _ => {}
+        }
+    }
+    if timeout.is_some_and(|t| usage.duration >= t) {
+        warn!(?command, "Game timed out and was killed");
+    }
+    Ok((status, output, usage))
+}
@@ -0,0 +1,55 @@
+use std::{env, path::Path, process::Command, str::FromStr};
+
+/// Wrapper isolating each game process from the rest of the machine
+#[derive(Clone, Debug)]
+pub enum Sandbox {
+    /// Bubblewrap with a read-only view of the whole filesystem, a private `/tmp` and no network
+    Bubblewrap,
+    /// User-provided command the game command line is appended to
+    Command(Vec<String>),
+}
+
+impl FromStr for Sandbox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("expected `bwrap` or a wrapper command".to_owned()),
+            "bwrap" => Ok(Self::Bubblewrap),
+            s => Ok(Self::Command(
+                s.split_whitespace().map(str::to_owned).collect(),
+            )),
+        }
+    }
+}
+
+impl Sandbox {
+    /// Command running `program` with `args` inside the sandbox. The game runs in `workdir`, which
+    /// is kept writable, or in the current directory if there's none
+    pub fn command(&self, program: &Path, args: &[String], workdir: Option<&Path>) -> Command {
+        let mut command = match self {
+            Self::Bubblewrap => {
+                let mut command = Command::new("bwrap");
+                command
+                    .args(["--ro-bind", "/", "/"])
+                    .args(["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"])
+                    .args(["--unshare-all", "--die-with-parent"]);
+                if let Some(workdir) = workdir {
+                    command.arg("--bind").arg(workdir).arg(workdir);
+                }
+                command.arg("--chdir").arg(
+                    workdir
+                        .map_or_else(|| env::current_dir().unwrap_or_default(), Path::to_path_buf),
+                );
+                command
+            }
+            Self::Command(wrapper) => {
+                let mut command = Command::new(&wrapper[0]);
+                command.args(&wrapper[1..]);
+                command
+            }
+        };
+        command.arg(program).args(args);
+        command
+    }
+}
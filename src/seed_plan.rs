@@ -0,0 +1,222 @@
+//! Which seeds every configuration of a sweep, table of a tournament or mix of a gauntlet plays,
+//! to check they play the seeds they're meant to and to keep a record of it.
+
+use crate::json::Json;
+
+use color_eyre::eyre::Result;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// How the seeds are meant to be split between the parts of the plan
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Every part plays the same seeds, so their results are over the same games
+    Shared,
+    /// The parts play one range after the other, without playing any seed twice
+    Disjoint,
+}
+
+struct Part {
+    label: String,
+    first: u32,
+    count: u32,
+}
+
+impl Part {
+    /// The seeds as ranges of `u64`, split in two if they wrap around
+    fn ranges(&self) -> Vec<(u64, u64)> {
+        let start = u64::from(self.first);
+        let end = start + u64::from(self.count);
+        let wrap = 1 << 32;
+        if end <= wrap {
+            vec![(start, end)]
+        } else {
+            vec![(start, wrap), (0, end - wrap)]
+        }
+    }
+
+    fn last(&self) -> u32 {
+        self.first.wrapping_add(self.count.saturating_sub(1))
+    }
+}
+
+/// The seed ranges played by the parts of a run, in the order they're played
+pub struct SeedPlan {
+    /// What the parts of the plan are called, like `tables` or `mixes`
+    kind: &'static str,
+    layout: Layout,
+    parts: Vec<Part>,
+}
+
+fn range(start: u64, end: u64) -> String {
+    if end - start == 1 {
+        format!("seed {start}")
+    } else {
+        format!("seeds {start} to {}", end - 1)
+    }
+}
+
+impl SeedPlan {
+    pub fn new(kind: &'static str, layout: Layout) -> Self {
+        Self {
+            kind,
+            layout,
+            parts: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, label: impl Into<String>, first: u32, count: u32) {
+        self.parts.push(Part {
+            label: label.into(),
+            first,
+            count,
+        });
+    }
+
+    /// Seeds played by at least one part of the plan
+    fn distinct_seeds(&self) -> u64 {
+        let mut ranges: Vec<(u64, u64)> = self.parts.iter().flat_map(Part::ranges).collect();
+        ranges.sort_unstable();
+        let mut total = 0;
+        let mut covered = 0;
+        for (start, end) in ranges {
+            let start = start.max(covered);
+            if end > start {
+                total += end - start;
+                covered = end;
+            }
+        }
+        total
+    }
+
+    /// Everything in the plan that doesn't follow its layout
+    pub fn problems(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        match self.layout {
+            Layout::Shared => {
+                let Some(first) = self.parts.first() else {
+                    return ret;
+                };
+                for part in &self.parts[1..] {
+                    if (part.first, part.count) != (first.first, first.count) {
+                        ret.push(format!(
+                            "{} plays seeds {} to {}, not the {} to {} of {}, so their results aren't over the same games",
+                            part.label,
+                            part.first,
+                            part.last(),
+                            first.first,
+                            first.last(),
+                            first.label
+                        ));
+                    }
+                }
+            }
+            Layout::Disjoint => {
+                for (i, a) in self.parts.iter().enumerate() {
+                    for b in &self.parts[i + 1..] {
+                        for (a_start, a_end) in a.ranges() {
+                            for (b_start, b_end) in b.ranges() {
+                                let (start, end) = (a_start.max(b_start), a_end.min(b_end));
+                                if start < end {
+                                    ret.push(format!(
+                                        "{} and {} both play {}",
+                                        a.label,
+                                        b.label,
+                                        range(start, end)
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+                for (a, b) in self.parts.iter().zip(&self.parts[1..]) {
+                    let next = a.first.wrapping_add(a.count);
+                    let skipped = b.first.wrapping_sub(next);
+                    // Going back to earlier seeds is an overlap, already reported
+                    if skipped > 0 && skipped <= u32::MAX / 2 {
+                        let start = u64::from(next);
+                        ret.push(format!(
+                            "None of the {} plays {}, skipped between {} and {}",
+                            self.kind,
+                            range(start, start + u64::from(skipped)),
+                            a.label,
+                            b.label
+                        ));
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Writes how many seeds the plan covers and what's wrong with it
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let (Some(min), Some(max)) = (
+            self.parts.iter().map(|p| p.first).min(),
+            self.parts.iter().map(Part::last).max(),
+        ) else {
+            return Ok(());
+        };
+        writeln!(
+            out,
+            "Seed plan: {} {} over {} distinct seeds, from {min} to {max}",
+            self.parts.len(),
+            self.kind,
+            self.distinct_seeds()
+        )?;
+        for problem in self.problems() {
+            writeln!(out, "=> {problem}")?;
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Json {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| {
+                Json::object([
+                    ("label", part.label.as_str().into()),
+                    ("first_seed", part.first.into()),
+                    ("last_seed", part.last().into()),
+                    ("instances", part.count.into()),
+                ])
+            })
+            .collect();
+        Json::object([
+            ("kind", self.kind.into()),
+            (
+                "layout",
+                match self.layout {
+                    Layout::Shared => "shared",
+                    Layout::Disjoint => "disjoint",
+                }
+                .into(),
+            ),
+            (
+                "distinct_seeds",
+                u32::try_from(self.distinct_seeds())
+                    .unwrap_or(u32::MAX)
+                    .into(),
+            ),
+            ("parts", Json::Array(parts)),
+            (
+                "problems",
+                Json::Array(self.problems().into_iter().map(Json::from).collect()),
+            ),
+        ])
+    }
+
+    /// Saves the plan to `path`, if given, after writing it to `out`
+    pub fn report(&self, out: &mut impl Write, path: Option<&Path>) -> Result<()> {
+        self.write(out)?;
+        if let Some(path) = path {
+            fs::write(path, self.to_json().to_string() + "\n")?;
+            writeln!(out, "Seed plan saved in {}", path.display())?;
+        }
+        Ok(())
+    }
+}
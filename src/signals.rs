@@ -0,0 +1,45 @@
+//! `SIGUSR1` asks for the statistics of the running games, as in `kill -USR1 <pid>` from another
+//! terminal, without stopping the run.
+
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static STATS_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn request_stats(_signal: libc::c_int) {
+    // Only an atomic store is safe in a signal handler
+    STATS_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Installs the handler of `SIGUSR1`, which would kill the process otherwise
+#[cfg(unix)]
+pub fn install() -> io::Result<()> {
+    // SAFETY: sigaction is a plain C struct, for which all zeroes is a valid value, and the
+    // handler only touches an atomic. SA_RESTART keeps the signal from interrupting the reads of
+    // the game outputs
+    let ret = unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = request_stats as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&raw mut action.sa_mask);
+        libc::sigaction(libc::SIGUSR1, &raw const action, std::ptr::null_mut())
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() -> io::Result<()> {
+    Ok(())
+}
+
+/// Whether the statistics were asked for since the last call
+pub fn take_stats_request() -> bool {
+    STATS_REQUESTED.swap(false, Ordering::Relaxed)
+}
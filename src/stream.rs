@@ -0,0 +1,84 @@
+use crate::json::Json;
+use crate::runner::{ExecutionResults, GameObserver};
+
+use color_eyre::eyre::Result;
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+/// Writes one NDJSON line per finished game as the run progresses
+pub struct ResultStream {
+    out: Mutex<Box<dyn Write + Send>>,
+    players: [String; 4],
+}
+
+impl ResultStream {
+    /// Opens the stream on a file, or on stdout if the path is `-`
+    pub fn open(path: &Path, players: &[String; 4]) -> io::Result<Self> {
+        let out: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(BufWriter::new(File::create(path)?))
+        };
+        Ok(Self {
+            out: Mutex::new(out),
+            players: players.clone(),
+        })
+    }
+}
+
+impl GameObserver for ResultStream {
+    fn game_finished(&self, result: &ExecutionResults) -> Result<()> {
+        let duration_ms = (result.duration().as_secs_f64() * 1e6).round() / 1e3;
+        let line = match result {
+            ExecutionResults::Ok { seed, points, .. } => {
+                let max = points.iter().max().copied().unwrap_or_default();
+                let winners: Vec<_> = (0..4)
+                    .filter(|&i| points[i] == max)
+                    .map(|i| Json::from(self.players[i].as_str()))
+                    .collect();
+                Json::object([
+                    ("seed", (*seed).into()),
+                    ("crashed", false.into()),
+                    ("scores", (*points).into()),
+                    ("winners", Json::Array(winners)),
+                    ("duration_ms", duration_ms.into()),
+                ])
+            }
+            ExecutionResults::Crash { seed, kind, .. } => Json::object([
+                ("seed", (*seed).into()),
+                ("crashed", true.into()),
+                ("crash_kind", kind.id().into()),
+                ("scores", Json::Null),
+                ("winners", Json::Array(Vec::new())),
+                ("duration_ms", duration_ms.into()),
+            ]),
+            ExecutionResults::ParseError { seed, .. } => Json::object([
+                ("seed", (*seed).into()),
+                ("crashed", false.into()),
+                ("parse_error", true.into()),
+                ("scores", Json::Null),
+                ("duration_ms", duration_ms.into()),
+            ]),
+            // Not a crash of the players, so readers that don't know about it can skip it
+            ExecutionResults::Unstarted { seed } => Json::object([
+                ("seed", (*seed).into()),
+                ("crashed", false.into()),
+                ("unstarted", true.into()),
+                ("scores", Json::Null),
+            ]),
+        };
+
+        let mut out = self
+            .out
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        writeln!(out, "{line}")?;
+        out.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use std::str::FromStr;
+
+/// `KEY=VALUE` assignment for the games. A comma-separated list of values sweeps over them,
+/// running the tests once for each
+#[derive(Clone, Debug)]
+pub struct Assignment {
+    pub key: String,
+    pub values: Vec<String>,
+}
+
+impl FromStr for Assignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, values)) if !key.is_empty() => Ok(Self {
+                key: key.to_owned(),
+                values: values.split(',').map(str::to_owned).collect(),
+            }),
+            _ => Err(format!("expected `KEY=VALUE`, got `{s}`")),
+        }
+    }
+}
+
+impl Assignment {
+    fn is_swept(&self) -> bool {
+        self.values.len() > 1
+    }
+}
+
+/// Values of the environment variables and settings for one run of the sweep
+#[derive(Clone, Debug, Default)]
+pub struct Combination {
+    pub env: Vec<(String, String)>,
+    pub settings: Vec<(String, String)>,
+}
+
+impl Combination {
+    /// Only the swept values, as shown to the user
+    pub fn describe(&self, env: &[Assignment], settings: &[Assignment]) -> String {
+        let swept = |assignments: &[Assignment], key: &str| {
+            assignments.iter().any(|a| a.key == key && a.is_swept())
+        };
+        let env = self
+            .env
+            .iter()
+            .filter(|(key, _)| swept(env, key))
+            .map(|(key, value)| format!("{key}={value}"));
+        let settings = self
+            .settings
+            .iter()
+            .filter(|(key, _)| swept(settings, key))
+            .map(|(key, value)| format!("setting {key}={value}"));
+        env.chain(settings).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// Whether any of the assignments has more than one value
+pub fn is_sweep(env: &[Assignment], settings: &[Assignment]) -> bool {
+    env.iter().chain(settings).any(Assignment::is_swept)
+}
+
+fn expand(assignments: &[Assignment]) -> Vec<Vec<(String, String)>> {
+    assignments
+        .iter()
+        .fold(vec![Vec::new()], |combinations, assignment| {
+            combinations
+                .iter()
+                .flat_map(|combination| {
+                    assignment.values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push((assignment.key.clone(), value.clone()));
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+/// Every combination of the swept values, varying the last variable fastest
+pub fn combinations(env: &[Assignment], settings: &[Assignment]) -> Vec<Combination> {
+    let settings = expand(settings);
+    expand(env)
+        .into_iter()
+        .flat_map(|env| {
+            settings.iter().map(move |settings| Combination {
+                env: env.clone(),
+                settings: settings.clone(),
+            })
+        })
+        .collect()
+}
+
+/// `KEY=VALUE` pairs separated by spaces
+pub fn describe(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Replaces the value of every `KEY value` line of the settings file for an overridden key,
+/// appending the keys that weren't there
+pub fn override_settings(settings: &str, overrides: &[(String, String)]) -> String {
+    let mut lines: Vec<String> = settings.lines().map(str::to_owned).collect();
+    for (key, value) in overrides {
+        let mut found = false;
+        for line in &mut lines {
+            if line.split_whitespace().next() == Some(key) {
+                *line = format!("{key} {value}");
+                found = true;
+            }
+        }
+        if !found {
+            lines.push(format!("{key} {value}"));
+        }
+    }
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,79 @@
+use crate::results::TestResults;
+
+use std::str::FromStr;
+
+/// Split of the seats in two teams, given as the seats of the first team (`1,2`). The rest of the
+/// seats make up the second team
+#[derive(Clone, Copy, Debug)]
+pub struct Teams {
+    first: [bool; 4],
+}
+
+impl FromStr for Teams {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("expected the seats of the first team, like `1,2`, got `{s}`");
+        let mut first = [false; 4];
+        for seat in s.split(',') {
+            let seat: usize = seat.trim().parse().map_err(|_| err())?;
+            if !(1..=4).contains(&seat) || first[seat - 1] {
+                return Err(err());
+            }
+            first[seat - 1] = true;
+        }
+        if first.iter().all(|&f| f) {
+            return Err(err());
+        }
+        Ok(Self { first })
+    }
+}
+
+/// Results of a team over the whole run
+pub struct TeamResults {
+    /// Seats of the team, from 0
+    pub seats: Vec<usize>,
+    /// Average combined score
+    pub average: f64,
+    /// Games won with the highest combined score, ties included
+    pub wins: u32,
+}
+
+impl Teams {
+    pub fn results(self, results: &TestResults) -> [TeamResults; 2] {
+        let team =
+            |first: bool| -> Vec<usize> { (0..4).filter(|&s| self.first[s] == first).collect() };
+        let seats = [team(true), team(false)];
+
+        let mut points = [0u64; 2];
+        let mut wins = [0; 2];
+        for scores in results.games.iter().filter_map(|g| g.points) {
+            let totals = seats
+                .each_ref()
+                .map(|seats| seats.iter().map(|&s| u64::from(scores[s])).sum::<u64>());
+            for team in 0..2 {
+                points[team] += totals[team];
+                if totals[team] >= totals[1 - team] {
+                    wins[team] += 1;
+                }
+            }
+        }
+
+        let games = f64::from(results.ok_games().max(1));
+        let [first, second] = seats;
+        #[allow(clippy::cast_precision_loss)] // Correctness: Only used for display
+        let average = |team: usize| points[team] as f64 / games;
+        [
+            TeamResults {
+                seats: first,
+                average: average(0),
+                wins: wins[0],
+            },
+            TeamResults {
+                seats: second,
+                average: average(1),
+                wins: wins[1],
+            },
+        ]
+    }
+}
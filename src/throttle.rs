@@ -0,0 +1,112 @@
+//! Keeps the games from oversubscribing the CPU, which makes the players exceed their CPU time
+//! for reasons that have nothing to do with them.
+
+use crate::usage::ResourceUsage;
+
+use std::{
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+/// How often a game waiting for a slot checks the system load again
+const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Weight of the last finished game in the estimate of the CPUs a game keeps busy
+const SMOOTHING: f64 = 0.2;
+
+struct ThrottleState {
+    running: u32,
+    /// CPUs a game keeps busy, from the CPU time of the finished ones over their duration
+    cpus_per_game: f64,
+}
+
+/// Limits the games running at once so they, along with the rest of the system, keep at most
+/// `target` CPUs busy
+pub struct Throttle {
+    target: f64,
+    state: Mutex<ThrottleState>,
+    released: Condvar,
+}
+
+/// Running game, which frees its slot when dropped
+pub struct Slot<'a> {
+    throttle: &'a Throttle,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.throttle.lock().running -= 1;
+        self.throttle.released.notify_all();
+    }
+}
+
+/// Load average of the last minute, which counts the running games too
+#[cfg(unix)]
+fn system_load() -> Option<f64> {
+    let mut load = 0.;
+    // SAFETY: The pointer is valid for writing a single value
+    (unsafe { libc::getloadavg(&raw mut load, 1) } == 1).then_some(load)
+}
+
+#[cfg(not(unix))]
+fn system_load() -> Option<f64> {
+    None
+}
+
+impl Throttle {
+    pub fn new(target: f64) -> Self {
+        Self {
+            target,
+            state: Mutex::new(ThrottleState {
+                running: 0,
+                cpus_per_game: 1.,
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ThrottleState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Games that can run at once, leaving room for the load that doesn't come from them. At
+    /// least one game always runs, so the run can't stall
+    fn allowed(&self, state: &ThrottleState) -> u32 {
+        let ours = f64::from(state.running) * state.cpus_per_game;
+        let others = system_load().map_or(0., |load| (load - ours).max(0.));
+        let allowed = ((self.target - others) / state.cpus_per_game).floor();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        // Correctness: Clamped to the range of u32
+        let allowed = allowed.clamp(1., f64::from(u32::MAX)) as u32;
+        allowed
+    }
+
+    /// Waits until a game can start without going over the target
+    pub fn acquire(&self) -> Slot<'_> {
+        let mut state = self.lock();
+        while state.running >= self.allowed(&state) {
+            state = self
+                .released
+                .wait_timeout(state, RECHECK_INTERVAL)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        state.running += 1;
+        Slot { throttle: self }
+    }
+
+    /// Learns how many CPUs a game keeps busy from one that finished, as the game or the players
+    /// may run several threads
+    pub fn record(&self, usage: &ResourceUsage) {
+        let Some(cpu) = usage.cpu else {
+            return;
+        };
+        let duration = usage.duration.as_secs_f64();
+        if duration <= 0. {
+            return;
+        }
+        let cpus = (cpu.total().as_secs_f64() / duration).max(0.1);
+        let mut state = self.lock();
+        state.cpus_per_game = state.cpus_per_game * (1. - SMOOTHING) + cpus * SMOOTHING;
+    }
+}
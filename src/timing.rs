@@ -0,0 +1,106 @@
+use crate::results::TestResults;
+use crate::usage::CpuTime;
+
+use std::time::Duration;
+
+/// Games taking this many times the median duration are flagged as slow
+const SLOW_FACTOR: u32 = 2;
+
+/// Games using this many times the median peak memory are flagged
+const HEAVY_FACTOR: u64 = 2;
+
+pub struct DurationStats {
+    pub min: Duration,
+    pub average: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+    /// Seeds that took anomalously long, slowest first
+    pub slow_seeds: Vec<(u32, Duration)>,
+}
+
+impl DurationStats {
+    pub fn compute(results: &TestResults) -> Option<Self> {
+        let mut durations: Vec<_> = results.games.iter().map(|g| g.usage.duration).collect();
+        durations.sort_unstable();
+
+        let count = u32::try_from(durations.len()).ok().filter(|&c| c > 0)?;
+        let percentile = |p: usize| durations[(durations.len() * p).div_ceil(100).max(1) - 1];
+        let median = percentile(50);
+
+        let mut slow_seeds: Vec<_> = results
+            .games
+            .iter()
+            .filter(|g| g.usage.duration > median * SLOW_FACTOR)
+            .map(|g| (g.seed, g.usage.duration))
+            .collect();
+        slow_seeds.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        Some(Self {
+            min: durations[0],
+            average: durations.iter().sum::<Duration>() / count,
+            p95: percentile(95),
+            max: durations[durations.len() - 1],
+            slow_seeds,
+        })
+    }
+}
+
+/// CPU time spent by the game processes, over the games whose platform reported it
+pub struct CpuStats {
+    pub total: CpuTime,
+    pub average: Duration,
+}
+
+impl CpuStats {
+    pub fn compute(results: &TestResults) -> Option<Self> {
+        let times: Vec<_> = results.games.iter().filter_map(|g| g.usage.cpu).collect();
+        let count = u32::try_from(times.len()).ok().filter(|&c| c > 0)?;
+
+        let total = times.iter().fold(CpuTime::default(), |acc, t| CpuTime {
+            user: acc.user + t.user,
+            system: acc.system + t.system,
+        });
+        Some(Self {
+            total,
+            average: total.total() / count,
+        })
+    }
+}
+
+/// Peak memory of the game processes, over the games whose platform reported it
+pub struct MemoryStats {
+    pub min: u64,
+    pub average: u64,
+    pub max: u64,
+    /// Seeds whose peak memory was anomalously high, heaviest first
+    pub heavy_seeds: Vec<(u32, u64)>,
+}
+
+impl MemoryStats {
+    pub fn compute(results: &TestResults) -> Option<Self> {
+        let mut peaks: Vec<_> = results
+            .games
+            .iter()
+            .filter_map(|g| g.usage.max_rss)
+            .collect();
+        peaks.sort_unstable();
+
+        let count = u64::try_from(peaks.len()).ok().filter(|&c| c > 0)?;
+        let median = peaks[peaks.len().div_ceil(2) - 1];
+
+        let mut heavy_seeds: Vec<_> = results
+            .games
+            .iter()
+            .filter_map(|g| Some((g.seed, g.usage.max_rss?)))
+            .filter(|&(_, rss)| rss > median * HEAVY_FACTOR)
+            .collect();
+        heavy_seeds.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        Some(Self {
+            min: peaks[0],
+            average: peaks.iter().sum::<u64>() / count,
+            max: peaks[peaks.len() - 1],
+            heavy_seeds,
+        })
+    }
+}
@@ -0,0 +1,323 @@
+//! Tournaments over a pool of more than four players, either round-robin or Swiss.
+
+use crate::errors::AppError;
+use crate::opponents::{Breakdown, Record};
+use crate::seed_plan::{Layout, SeedPlan};
+use crate::{parse_players, TestConfig};
+
+use color_eyre::eyre::Result;
+use itertools::Itertools;
+use std::{
+    cmp::{Ordering, Reverse},
+    fs, io,
+    num::NonZeroU32,
+    path::PathBuf,
+};
+
+/// Totals of a player over every table it sat at
+#[derive(Default)]
+struct Standing {
+    tables: u32,
+    games: u32,
+    points: u64,
+    wins: u32,
+    crashes: u32,
+    /// Games finished at every place, with tied players sharing the best of their places
+    places: [u32; 4],
+}
+
+impl Standing {
+    fn win_rate(&self) -> f64 {
+        f64::from(self.wins) * 100. / f64::from(self.games.max(1))
+    }
+
+    fn average(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)] // Correctness: Only used for display
+        let points = self.points as f64;
+        points / f64::from(self.games.max(1))
+    }
+
+    /// Orders the standings from best to worst
+    fn rank(&self, other: &Self) -> Ordering {
+        other
+            .win_rate()
+            .total_cmp(&self.win_rate())
+            .then(other.average().total_cmp(&self.average()))
+    }
+}
+
+/// Pool of players and the seeds every table plays
+pub struct Tournament {
+    pub players: Vec<String>,
+    pub seed: u32,
+    pub instances: NonZeroU32,
+    /// Number of Swiss rounds, or `None` for a round-robin
+    pub swiss: Option<NonZeroU32>,
+    pub settings_file: String,
+    pub game: PathBuf,
+    /// Only print the tables and their seeds
+    pub dry_run: bool,
+    /// File the seeds of every table are written to
+    pub seed_plan: Option<PathBuf>,
+    /// Player whose results are broken down by the opponents at its tables
+    pub focus: Option<String>,
+    /// File the classification is exported to, in the format of the official rankings
+    pub classification: Option<PathBuf>,
+    /// Classification points of the first to the last place of every game
+    pub placement_points: [u32; 4],
+}
+
+impl Tournament {
+    /// Plays the tables of the tournament, then ranks the players by win rate
+    pub fn run(&self) -> Result<()> {
+        if self.players.len() < 4 {
+            return Err(AppError::NotEnoughPlayers(self.players.len()).into());
+        }
+
+        let focus = self
+            .focus
+            .as_ref()
+            .map(|name| {
+                self.players
+                    .iter()
+                    .position(|p| p == name)
+                    .ok_or_else(|| AppError::UnknownPlayer(name.clone()))
+            })
+            .transpose()?;
+
+        let mut standings: Vec<Standing> =
+            self.players.iter().map(|_| Standing::default()).collect();
+        let mut breakdown = Breakdown::default();
+        let mut play = |table: &[usize], seed: u32, standings: &mut [Standing]| {
+            self.play(table, seed, standings, focus.map(|f| (f, &mut breakdown)))
+        };
+        let seed_plan = if let Some(rounds) = self.swiss {
+            self.swiss(rounds, &mut standings, &mut play)?
+        } else {
+            // Every table of four players of the pool plays the same seed set
+            let mut seed_plan = SeedPlan::new("tables", Layout::Shared);
+            let tables: Vec<Vec<usize>> = (0..self.players.len()).combinations(4).collect();
+            for (i, table) in tables.iter().enumerate() {
+                print!("Table {}/{}: ", i + 1, tables.len());
+                seed_plan.record(format!("table {}", i + 1), self.seed, self.instances.get());
+                play(table, self.seed, &mut standings)?;
+            }
+            seed_plan
+        };
+        seed_plan.report(&mut io::stdout(), self.seed_plan.as_deref())?;
+
+        if self.dry_run {
+            println!(
+                "Every table plays {} seeds, starting from seed {}{}",
+                self.instances,
+                self.seed,
+                if self.swiss.is_some() {
+                    " and moving on every round"
+                } else {
+                    ""
+                }
+            );
+            return Ok(());
+        }
+        self.print_ranking(&standings);
+        if let Some(path) = &self.classification {
+            fs::write(path, self.classification(&standings))?;
+            println!("Classification saved in {}", path.display());
+        }
+        if let Some(focus) = focus {
+            println!();
+            breakdown.print(&self.players[focus]);
+        }
+        Ok(())
+    }
+
+    /// Pairs the players with the closest standings at every round, which needs far fewer tables
+    /// than a round-robin to tell the best players apart. The tables of a round play the same
+    /// seeds, so the plan is of the rounds
+    fn swiss(
+        &self,
+        rounds: NonZeroU32,
+        standings: &mut [Standing],
+        play: &mut impl FnMut(&[usize], u32, &mut [Standing]) -> Result<()>,
+    ) -> Result<SeedPlan> {
+        // New seeds every round, as the same table would replay the same games otherwise
+        let round_seed = |round: u32| {
+            self.seed
+                .wrapping_add(round.wrapping_mul(self.instances.get()))
+        };
+        let mut seed_plan = SeedPlan::new("rounds", Layout::Disjoint);
+        for round in 0..rounds.get() {
+            seed_plan.record(
+                format!("round {}", round + 1),
+                round_seed(round),
+                self.instances.get(),
+            );
+        }
+
+        let mut byes = vec![0u32; self.players.len()];
+        for round in 0..rounds.get() {
+            // The sort is stable, so the first round keeps the order of the pool
+            let mut order: Vec<usize> = (0..self.players.len())
+                .sorted_by(|&a, &b| standings[a].rank(&standings[b]))
+                .collect();
+
+            // The players that don't fit in a table sit the round out, taking turns from the
+            // bottom of the ranking
+            let mut sitting_out = Vec::new();
+            for _ in 0..self.players.len() % 4 {
+                let (i, &player) = order
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .min_by_key(|(_, &p)| byes[p])
+                    .expect("there are more players than tables can seat");
+                byes[player] += 1;
+                sitting_out.push(self.players[player].as_str());
+                order.remove(i);
+            }
+
+            let seed = round_seed(round);
+            let tables = order.len() / 4;
+            for (i, table) in order.chunks(4).enumerate() {
+                print!("Round {}/{}, table {}/{tables}: ", round + 1, rounds, i + 1);
+                play(table, seed, standings)?;
+            }
+            if !sitting_out.is_empty() {
+                println!(
+                    "Sitting out round {}: {}",
+                    round + 1,
+                    sitting_out.join(", ")
+                );
+            }
+            if self.dry_run {
+                println!("The next rounds pair the players by their standings after each round");
+                break;
+            }
+        }
+        Ok(seed_plan)
+    }
+
+    /// Plays `seed` and the following seeds at a table, adding the results to the standings and,
+    /// if the focal player sat at it, to its breakdown
+    fn play(
+        &self,
+        table: &[usize],
+        seed: u32,
+        standings: &mut [Standing],
+        focus: Option<(usize, &mut Breakdown)>,
+    ) -> Result<()> {
+        let names: [String; 4] = [0, 1, 2, 3].map(|seat| self.players[table[seat]].clone());
+        println!("{}", names.join(", "));
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let mut config = TestConfig::new(
+            parse_players(names),
+            self.settings_file.clone(),
+            self.game.clone(),
+        );
+        config.seed = seed;
+        config.instances = self.instances;
+        config.quiet = true;
+        let results = crate::run_tests(&config)?;
+
+        for (seat, &player) in table.iter().enumerate() {
+            let standing = &mut standings[player];
+            let player_results = &results.player_results[seat];
+            standing.tables += 1;
+            standing.games += results.ok_games();
+            standing.points += u64::from(player_results.total_points);
+            standing.wins += player_results.total_wins;
+            #[allow(clippy::cast_possible_truncation)]
+            // Correctness: We can't run more than u32::MAX seeds
+            let crashes = results.failed_seeds.len() as u32;
+            standing.crashes += crashes;
+        }
+        for game in &results.games {
+            let Some(points) = game.points else {
+                continue;
+            };
+            for (seat, &player) in table.iter().enumerate() {
+                let place = points.iter().filter(|&&p| p > points[seat]).count();
+                standings[player].places[place] += 1;
+            }
+        }
+        if let Some((focus, breakdown)) = focus {
+            if let Some(seat) = table.iter().position(|&p| p == focus) {
+                let opponents: Vec<String> = table
+                    .iter()
+                    .filter(|&&p| p != focus)
+                    .map(|&p| self.players[p].clone())
+                    .collect();
+                breakdown.add(&opponents, &Record::of(&results, seat));
+            }
+        }
+        Ok(())
+    }
+
+    /// The standings as the official rankings publish them: a CSV line per player, ranked by
+    /// the classification points of their places, then by their total score
+    fn classification(&self, standings: &[Standing]) -> String {
+        let rows: Vec<_> = self
+            .players
+            .iter()
+            .zip(standings)
+            .map(|(name, standing)| {
+                let points: u64 = standing
+                    .places
+                    .iter()
+                    .zip(self.placement_points)
+                    .map(|(&games, points)| u64::from(games) * u64::from(points))
+                    .sum();
+                (name, standing, points)
+            })
+            .sorted_by_key(|&(_, standing, points)| Reverse((points, standing.points)))
+            .collect();
+
+        let mut lines =
+            vec!["position,player,points,score,games,first,second,third,fourth".to_owned()];
+        for (i, &(name, standing, points)) in rows.iter().enumerate() {
+            // Players with the same points and score share the position
+            let position = rows
+                .iter()
+                .position(|&(_, s, p)| (p, s.points) == (points, standing.points))
+                .unwrap_or(i)
+                + 1;
+            lines.push(format!(
+                "{position},{name},{points},{},{},{}",
+                standing.points,
+                standing.games,
+                standing.places.iter().join(",")
+            ));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    fn print_ranking(&self, standings: &[Standing]) {
+        let ranking: Vec<_> = self
+            .players
+            .iter()
+            .zip(standings)
+            .sorted_by(|(_, a), (_, b)| a.rank(b))
+            .collect();
+
+        println!();
+        println!(
+            "{:>4}  {:<20} {:>6} {:>6} {:>10} {:>8} {:>7}",
+            "Rank", "Player", "Tables", "Games", "Avg score", "WR", "Crashes"
+        );
+        for (rank, (name, standing)) in ranking.into_iter().enumerate() {
+            println!(
+                "{:>4}  {:<20} {:>6} {:>6} {:>10.1} {:>7.1}% {:>7}",
+                rank + 1,
+                name,
+                standing.tables,
+                standing.games,
+                standing.average(),
+                standing.win_rate(),
+                standing.crashes
+            );
+        }
+    }
+}
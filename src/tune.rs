@@ -0,0 +1,120 @@
+use crate::results::TestResults;
+use crate::sweep::Combination;
+use crate::{Sweep, TestConfig};
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use std::{fs, path::Path};
+
+/// Statistic of the focal player the configurations are ranked by
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum RankBy {
+    /// Average score
+    Score,
+    /// Win rate
+    WinRate,
+}
+
+struct Row<'a> {
+    combination: &'a Combination,
+    results: &'a TestResults,
+    /// Average score of every player
+    scores: [f64; 4],
+    /// Win rate of every player, as a percentage
+    win_rates: [f64; 4],
+}
+
+impl<'a> Row<'a> {
+    fn new(combination: &'a Combination, results: &'a TestResults) -> Self {
+        let games = f64::from(results.ok_games().max(1));
+        Self {
+            combination,
+            results,
+            scores: results
+                .player_results
+                .each_ref()
+                .map(|r| f64::from(r.total_points) / games),
+            win_rates: results
+                .player_results
+                .each_ref()
+                .map(|r| f64::from(r.total_wins) * 100. / games),
+        }
+    }
+}
+
+/// Prints the configurations of the sweep ranked by the focal player's results, and optionally
+/// exports the whole grid as CSV
+pub fn report(
+    config: &TestConfig,
+    sweep: &Sweep,
+    results: &[(Combination, TestResults)],
+    rank_by: RankBy,
+    grid: Option<&Path>,
+) -> Result<()> {
+    let seat = config.focus.unwrap_or(0);
+    let mut rows: Vec<_> = results.iter().map(|(c, r)| Row::new(c, r)).collect();
+    // Ties are broken by the other statistic
+    let key = |row: &Row| match rank_by {
+        RankBy::Score => (row.scores[seat], row.win_rates[seat]),
+        RankBy::WinRate => (row.win_rates[seat], row.scores[seat]),
+    };
+    rows.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        b.0.total_cmp(&a.0).then(b.1.total_cmp(&a.1))
+    });
+
+    println!(
+        "Configurations ranked by the {} of {}:",
+        match rank_by {
+            RankBy::Score => "average score",
+            RankBy::WinRate => "win rate",
+        },
+        config.players[seat].as_string()
+    );
+    println!(
+        "{:>4} {:>10} {:>8} {:>7}  Configuration",
+        "Rank", "Avg score", "WR", "Crashes"
+    );
+    for (rank, row) in rows.iter().enumerate() {
+        println!(
+            "{:>4} {:>10.1} {:>7.1}% {:>7}  {}",
+            rank + 1,
+            row.scores[seat],
+            row.win_rates[seat],
+            row.results.failed_seeds.len(),
+            row.combination.describe(&sweep.env, &sweep.settings)
+        );
+    }
+
+    if let Some(path) = grid {
+        fs::write(path, grid_csv(config, sweep, &rows))?;
+        println!("Grid exported to {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn grid_csv(config: &TestConfig, sweep: &Sweep, rows: &[Row]) -> String {
+    let names = config.players.map(|p| p.as_string());
+    let mut header: Vec<_> = sweep.env.iter().map(|a| a.key.clone()).collect();
+    header.extend(sweep.settings.iter().map(|a| format!("setting:{}", a.key)));
+    header.extend(names.iter().map(|n| format!("{n} score")));
+    header.extend(names.iter().map(|n| format!("{n} win rate")));
+    header.push("crashes".to_owned());
+
+    let mut lines = vec![header.join(",")];
+    for row in rows {
+        let mut fields: Vec<_> = row
+            .combination
+            .env
+            .iter()
+            .chain(&row.combination.settings)
+            .map(|(_, value)| value.clone())
+            .collect();
+        fields.extend(row.scores.iter().map(|s| format!("{s:.3}")));
+        fields.extend(row.win_rates.iter().map(|w| format!("{w:.3}")));
+        fields.push(row.results.failed_seeds.len().to_string());
+        lines.push(fields.join(","));
+    }
+    lines.join("\n") + "\n"
+}
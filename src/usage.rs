@@ -0,0 +1,137 @@
+use std::{
+    io,
+    process::{Child, ExitStatus},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often a game with a deadline or that can be cancelled is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// CPU time consumed by a game process
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuTime {
+    pub user: Duration,
+    pub system: Duration,
+}
+
+impl CpuTime {
+    pub fn total(&self) -> Duration {
+        self.user + self.system
+    }
+}
+
+/// Resources used by a game process
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResourceUsage {
+    /// Wall-clock duration of the game
+    pub duration: Duration,
+    /// CPU time, only available on platforms that report it
+    pub cpu: Option<CpuTime>,
+    /// Peak resident set size in bytes, only available on platforms that report it
+    pub max_rss: Option<u64>,
+}
+
+/// Waits for the child started at `start` to exit, collecting its resource usage. It gets killed
+/// if it's still running at `deadline`, or once `cancel` is set
+#[cfg(unix)]
+pub fn wait(
+    child: &mut Child,
+    start: Instant,
+    mut deadline: Option<Instant>,
+    mut cancel: Option<&AtomicBool>,
+) -> io::Result<(ExitStatus, ResourceUsage)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = libc::pid_t::try_from(child.id()).map_err(io::Error::other)?;
+    let mut status = 0;
+    // SAFETY: rusage is a plain C struct, for which all zeroes is a valid value
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    loop {
+        let options = if deadline.is_some() || cancel.is_some() {
+            libc::WNOHANG
+        } else {
+            0
+        };
+        // SAFETY: Both pointers are valid for writes, and the pid belongs to a child we haven't
+        // reaped yet
+        let ret = unsafe { libc::wait4(pid, &raw mut status, options, &raw mut rusage) };
+        if ret == pid {
+            break;
+        }
+        if ret == 0 {
+            // Still running, and not reaped yet, so killing it can't hit another process
+            if deadline.is_some_and(|d| Instant::now() >= d)
+                || cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+            {
+                // Kills whatever the game spawned too if it leads its own process group, as
+                // those could keep its stderr open
+                // SAFETY: Plain syscall, with no memory involved
+                if unsafe { libc::kill(-pid, libc::SIGKILL) } != 0 {
+                    child.kill()?;
+                }
+                deadline = None;
+                cancel = None;
+            } else {
+                thread::sleep(POLL_INTERVAL);
+            }
+            continue;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+
+    let to_duration = |t: libc::timeval| {
+        Duration::from_secs(t.tv_sec.try_into().unwrap_or_default())
+            + Duration::from_micros(t.tv_usec.try_into().unwrap_or_default())
+    };
+    // Linux reports the peak RSS in kilobytes, macOS in bytes
+    let rss_unit = if cfg!(target_os = "macos") { 1 } else { 1024 };
+    let usage = ResourceUsage {
+        duration: start.elapsed(),
+        cpu: Some(CpuTime {
+            user: to_duration(rusage.ru_utime),
+            system: to_duration(rusage.ru_stime),
+        }),
+        max_rss: u64::try_from(rusage.ru_maxrss)
+            .ok()
+            .map(|rss| rss * rss_unit),
+    };
+
+    Ok((ExitStatus::from_raw(status), usage))
+}
+
+/// Waits for the child started at `start` to exit, killing it if it's still running at
+/// `deadline` or once `cancel` is set. Only the duration is available on this platform
+#[cfg(not(unix))]
+pub fn wait(
+    child: &mut Child,
+    start: Instant,
+    deadline: Option<Instant>,
+    cancel: Option<&AtomicBool>,
+) -> io::Result<(ExitStatus, ResourceUsage)> {
+    let status = match (deadline, cancel) {
+        (None, None) => child.wait()?,
+        _ => loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d)
+                || cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+            {
+                child.kill()?;
+                break child.wait()?;
+            }
+            thread::sleep(POLL_INTERVAL);
+        },
+    };
+    let usage = ResourceUsage {
+        duration: start.elapsed(),
+        ..ResourceUsage::default()
+    };
+    Ok((status, usage))
+}
@@ -0,0 +1,68 @@
+use crate::errors::AppError;
+use crate::results::TestResults;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+/// Which game to open in the viewer after the run
+#[derive(Clone, Copy, Debug)]
+pub enum ViewerTarget {
+    Seed(u32),
+    /// The game with the narrowest margin between the winner and the runner-up
+    Closest,
+}
+
+impl FromStr for ViewerTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "closest" => Ok(Self::Closest),
+            s => s
+                .parse()
+                .map(Self::Seed)
+                .map_err(|_| format!("expected a seed or `closest`, got `{s}`")),
+        }
+    }
+}
+
+impl ViewerTarget {
+    pub fn resolve(self, results: &TestResults) -> Option<u32> {
+        match self {
+            Self::Seed(seed) => Some(seed),
+            Self::Closest => results
+                .games
+                .iter()
+                .filter_map(|g| Some((g.margin()?, g.seed)))
+                .min()
+                .map(|(_, seed)| seed),
+        }
+    }
+}
+
+/// Opens the HTML viewer on a saved game output
+pub fn open(viewer: &Path, output: &Path) -> Result<(), AppError> {
+    let absolute = |path: &Path| -> Result<PathBuf, AppError> {
+        path.canonicalize()
+            .map_err(|_| AppError::MissingFile(path.to_path_buf()))
+    };
+    let url = format!(
+        "file://{}?game={}",
+        absolute(viewer)?.display(),
+        absolute(output)?.display()
+    );
+
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(opener)
+        .arg(&url)
+        .spawn()
+        .map_err(|_| AppError::ViewerLaunch(url))?;
+    Ok(())
+}
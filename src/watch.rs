@@ -0,0 +1,92 @@
+use crate::build;
+use crate::regressions::{self, Scores};
+use crate::results::TestResults;
+use crate::TestConfig;
+
+use color_eyre::eyre::Result;
+use std::{
+    fs, io,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// How often the watched files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Average score and win rate of every player
+type Summary = [(f64, f64); 4];
+
+fn summarize(results: &TestResults) -> Summary {
+    let games = f64::from(results.ok_games().max(1));
+    results.player_results.each_ref().map(|r| {
+        (
+            f64::from(r.total_points) / games,
+            f64::from(r.total_wins) * 100. / games,
+        )
+    })
+}
+
+fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|f| fs::metadata(f).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Plays the seeds, then rebuilds the game and plays them again every time one of the watched
+/// files changes, printing how the results moved since the previous build
+pub fn run(config: &TestConfig, files: &[PathBuf]) -> Result<()> {
+    let mut config = config.clone();
+    config.quiet = true;
+    let names = config.players.map(|p| p.as_string());
+
+    let mut previous: Option<Summary> = None;
+    let mut previous_scores: Option<Scores> = None;
+    let mut stamps = modified(files);
+    loop {
+        let results = crate::run_tests(&config)?;
+        let summary = summarize(&results);
+        for (i, (score, win_rate)) in summary.iter().enumerate() {
+            let delta = previous.map_or_else(String::new, |previous| {
+                format!(
+                    " ({:+.1}, {:+.1}%)",
+                    score - previous[i].0,
+                    win_rate - previous[i].1
+                )
+            });
+            println!(
+                "=> {:<12} {score:>8.1} {win_rate:>5.1}% WR{delta}",
+                names[i]
+            );
+        }
+        if !results.failed_seeds.is_empty() {
+            println!("   {} games crashed", results.failed_seeds.len());
+        }
+        let scores = regressions::scores(&results);
+        if let Some(before) = &previous_scores {
+            let seat = config.focus.unwrap_or(0);
+            let drops = regressions::find(before, &scores, seat);
+            regressions::write(&mut io::stdout(), &drops, &names[seat], config.drops)?;
+        }
+        previous = Some(summary);
+        previous_scores = Some(scores);
+
+        // Broken builds are reported and then it waits for the next change
+        loop {
+            println!("Watching for changes...");
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                let current = modified(files);
+                if current != stamps {
+                    stamps = current;
+                    break;
+                }
+            }
+            match build::make() {
+                Ok(()) => break,
+                Err(err) => eprintln!("{err}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::bootstrap;
+use crate::errors::AppError;
+use crate::json::Json;
+use crate::notify;
+use crate::results::TestResults;
+use crate::TestConfig;
+
+use clap::ValueEnum;
+use color_eyre::eyre::Result;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Shape of the payload posted to the webhook
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum WebhookFormat {
+    /// The summary as structured JSON
+    Json,
+    /// A Slack message
+    Slack,
+    /// A Discord message
+    Discord,
+}
+
+/// Per-player averages, as posted in the structured payload
+fn players(config: &TestConfig, results: &TestResults) -> Json {
+    let games = f64::from(results.ok_games().max(1));
+    let players: Vec<_> = results
+        .player_results
+        .iter()
+        .zip(config.players)
+        .enumerate()
+        .map(|(seat, (res, name))| {
+            let interval = bootstrap::score_interval(results, &[seat])
+                .map_or(Json::Null, |(low, high)| {
+                    Json::Array(vec![low.into(), high.into()])
+                });
+            Json::object([
+                ("name", name.as_string().into()),
+                (
+                    "average_score",
+                    (f64::from(res.total_points) / games).into(),
+                ),
+                ("average_score_interval", interval),
+                ("win_rate", (f64::from(res.total_wins) / games).into()),
+                ("timeouts", res.timeouts.into()),
+                ("disqualifications", res.disqualifications.into()),
+                ("warnings", res.warnings.into()),
+            ])
+        })
+        .collect();
+    Json::Array(players)
+}
+
+/// Human-readable summary for chat messages, with the headline wrapped in `bold`
+fn message(config: &TestConfig, results: &TestResults, bold: &str) -> String {
+    let games = f64::from(results.ok_games().max(1));
+    let mut lines = vec![format!("{bold}{}{bold}", notify::headline(config, results))];
+    for (res, name) in results.player_results.iter().zip(config.players) {
+        lines.push(format!(
+            "• {}: {:.1} points, {:.1}% WR",
+            name.as_string(),
+            f64::from(res.total_points) / games,
+            f64::from(res.total_wins) * 100. / games
+        ));
+    }
+    lines.join("\n")
+}
+
+pub fn payload(config: &TestConfig, results: &TestResults, format: WebhookFormat) -> Json {
+    match format {
+        WebhookFormat::Json => Json::object([
+            ("first_seed", config.seed.into()),
+            ("instances", config.instances.get().into()),
+            ("games", results.ok_games().into()),
+            ("players", players(config, results)),
+            ("crashed_seeds", results.failed_seeds.clone().into()),
+            (
+                "crash_kinds",
+                Json::object(results.crash_kinds().into_iter().map(|(kind, games)| {
+                    (kind.id(), u32::try_from(games).unwrap_or(u32::MAX).into())
+                })),
+            ),
+            (
+                "git",
+                config
+                    .revision
+                    .as_ref()
+                    .map_or(Json::Null, crate::git::Revision::to_json),
+            ),
+            ("metadata", crate::metadata::collect(config)),
+        ]),
+        WebhookFormat::Slack => Json::object([("text", message(config, results, "*").into())]),
+        WebhookFormat::Discord => {
+            Json::object([("content", message(config, results, "**").into())])
+        }
+    }
+}
+
+/// Posts the summary of the run to `url`, using curl
+pub fn post(url: &str, payload: &Json) -> Result<()> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "30"])
+        .args([
+            "-H",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+        ])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or(AppError::BrokenChildCommunication)?
+        .write_all(payload.to_string().as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        return Err(AppError::Webhook(stderr).into());
+    }
+    Ok(())
+}
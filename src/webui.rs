@@ -0,0 +1,356 @@
+use crate::artifacts::RunArtifacts;
+use crate::errors::AppError;
+use crate::http::{self, Request, Response};
+use crate::metrics::Metrics;
+use crate::queue::Queue;
+
+use rand::Rng;
+use std::{
+    fs, io,
+    net::{SocketAddr, TcpListener},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    thread,
+};
+
+/// Options of `run` that can be queued from the web UI, leaving out the ones that run commands,
+/// set the environment of the games or write files
+const QUEUEABLE: &[&str] = &[
+    "-i",
+    "--instances",
+    "--mirror",
+    "--fill",
+    "--teams",
+    "--preset",
+    "--isolate",
+    "--setting",
+    "-s",
+    "--seed",
+    "--repeat",
+    "-g",
+    "--game-settings",
+    "--settings-via",
+    "--game-timeout",
+    "--cpu-budget",
+    "--judge-slowdown",
+    "--enforce-cpu-budget",
+    "--stderr-limit",
+    "--spawn-retries",
+    "--score-regex",
+    "--timeout-regex",
+    "--disqualified-regex",
+    "--warning-regex",
+    "--timeline",
+    "--round-regex",
+    "--round-score-regex",
+    "-m",
+    "--metric",
+    "--retain",
+    "--retain-count",
+    "--summary-only",
+    "--focus",
+    "--focus-count",
+    "--sort-by",
+    "--top",
+    "--normalize",
+    "--fail-on",
+    "--min-win-rate",
+    "--min-score",
+    "--fail-fast",
+];
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn page(title: &str, body: &str, refresh: bool) -> Response {
+    let refresh = if refresh {
+        r#"<meta http-equiv="refresh" content="2">"#
+    } else {
+        ""
+    };
+    Response::ok(
+        "text/html; charset=utf-8",
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\">{refresh}<title>{}</title>\
+             <style>body{{font-family:sans-serif;margin:2em}}td,th{{padding:0 1em;text-align:left}}\
+             pre{{background:#f4f4f4;padding:1em}}</style></head><body><h1>{}</h1>{body}</body></html>",
+            escape(title),
+            escape(title)
+        ),
+    )
+}
+
+/// Names of the entries of a directory, sorted
+fn list(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Decodes a value of an `application/x-www-form-urlencoded` form
+fn form_value(body: &str, name: &str) -> Option<String> {
+    let value = body
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))?;
+    let mut bytes = Vec::new();
+    let mut chars = value.bytes();
+    while let Some(b) = chars.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = chars.by_ref().take(2).collect();
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            b => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Joins a request path to `base`, refusing anything that could leave it
+fn resolve(base: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then(|| base.join(path))
+}
+
+/// Fails on the first argument that isn't a player name, a value or an option in `QUEUEABLE`
+fn check_queueable(args: &[String]) -> Result<(), AppError> {
+    match args.iter().find(|arg| {
+        arg.starts_with('-') && !QUEUEABLE.contains(&arg.split('=').next().unwrap_or_default())
+    }) {
+        Some(arg) => Err(AppError::UnqueueableArgument(arg.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Web pages showing the live progress of the current run and the artifacts of past runs
+struct WebUi {
+    runs: PathBuf,
+    live: Option<Arc<Metrics>>,
+    /// Queue the runs can be added to, played from the folder the UI was started in
+    queue: Option<(Queue, PathBuf)>,
+    /// Address the UI is served on, the only one the requests can be for
+    addr: SocketAddr,
+    /// Random value of the queue form, so that other sites can't post it
+    token: String,
+}
+
+impl WebUi {
+    /// Whether `host` names the address the UI is served on
+    fn serves(&self, host: &str) -> bool {
+        let port = self.addr.port();
+        host == self.addr.to_string()
+            || (self.addr.ip().is_loopback() && host == format!("localhost:{port}"))
+            || (self.addr.ip().is_unspecified()
+                && host.parse::<SocketAddr>().is_ok_and(|h| h.port() == port))
+    }
+
+    /// Whether the request is for this UI and comes from one of its pages, if from a page at all.
+    /// It keeps other sites from reaching it through DNS rebinding
+    fn same_origin(&self, request: &Request) -> bool {
+        request.header("host").is_none_or(|host| self.serves(host))
+            && request.header("origin").is_none_or(|origin| {
+                origin
+                    .strip_prefix("http://")
+                    .is_some_and(|host| self.serves(host))
+            })
+    }
+
+    fn handle(&self, request: &Request) -> Response {
+        if !self.same_origin(request) {
+            return Response::forbidden();
+        }
+        let path = request.path.as_str();
+        match path.trim_end_matches('/') {
+            "" => self.index(),
+            "/queue" if request.method == "POST" => self.enqueue(&request.body),
+            "/queue" => self.queue(),
+            "/metrics" => match &self.live {
+                Some(live) => Response::ok("text/plain; version=0.0.4", live.prometheus()),
+                None => Response::not_found(),
+            },
+            path => match path.strip_prefix("/runs/") {
+                Some(path) => self.run(path),
+                None => Response::not_found(),
+            },
+        }
+    }
+
+    fn index(&self) -> Response {
+        let mut body = Vec::new();
+        if let Some(live) = &self.live {
+            let snapshot = live.snapshot();
+            body.push(format!(
+                "<h2>Current run</h2><p><progress max=\"{}\" value=\"{}\"></progress> \
+                 {}/{} games, {} crashed, {:.2} games/s</p>",
+                snapshot.total,
+                snapshot.finished,
+                snapshot.finished,
+                snapshot.total,
+                snapshot.crashes,
+                snapshot.per_second
+            ));
+            body.push("<table><tr><th>Player</th><th>Average score</th><th>WR</th></tr>".into());
+            for i in 0..4 {
+                body.push(format!(
+                    "<tr><td>{}</td><td>{:.1}</td><td>{:.1}%</td></tr>",
+                    escape(&snapshot.names[i]),
+                    snapshot.average_scores[i],
+                    snapshot.win_rates[i] * 100.
+                ));
+            }
+            body.push("</table>".into());
+        }
+
+        if self.queue.is_some() {
+            body.push("<p><a href=\"/queue\">Queued runs</a></p>".into());
+        }
+        body.push("<h2>Completed runs</h2><ul>".into());
+        for run in list(&self.runs).iter().rev() {
+            if self.runs.join(run).join("manifest.json").exists() {
+                let run = escape(run);
+                body.push(format!("<li><a href=\"/runs/{run}\">{run}</a></li>"));
+            }
+        }
+        body.push("</ul>".into());
+
+        page("EDA game tester", &body.concat(), self.live.is_some())
+    }
+
+    /// The queued runs and a form to queue another one
+    fn queue(&self) -> Response {
+        let Some((queue, _)) = &self.queue else {
+            return Response::not_found();
+        };
+        let mut body = vec![
+            format!(
+                "<form method=\"post\" action=\"/queue\">\
+                 <input type=\"hidden\" name=\"token\" value=\"{}\">\
+                 <input name=\"args\" size=\"80\" \
+                 placeholder=\"Arguments of the run, separated by spaces\"> \
+                 <button>Queue</button></form>",
+                self.token
+            ),
+            "<table><tr><th>Run</th><th>State</th><th>Arguments</th></tr>".to_owned(),
+        ];
+        for request in queue.list() {
+            body.push(format!(
+                "<tr><td>{}</td><td>{}</td><td><code>{}</code></td></tr>",
+                escape(&request.id),
+                request.state.name(),
+                escape(&request.args.join(" "))
+            ));
+        }
+        body.push("</table>".into());
+        page("Queued runs", &body.concat(), true)
+    }
+
+    fn enqueue(&self, form: &str) -> Response {
+        let Some((queue, dir)) = &self.queue else {
+            return Response::not_found();
+        };
+        if form_value(form, "token").as_deref() != Some(self.token.as_str()) {
+            return Response::forbidden();
+        }
+        let args: Vec<String> = form_value(form, "args")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect();
+        let queued = check_queueable(&args)
+            .map_err(Into::into)
+            .and_then(|()| queue.add(dir, &args));
+        let message = match queued {
+            Ok(id) => format!("Queued {}", escape(&id)),
+            Err(err) => format!("Couldn't queue the run: {}", escape(&err.to_string())),
+        };
+        page(
+            "Queued runs",
+            &format!("<p>{message}</p><p><a href=\"/queue\">Back to the queue</a></p>"),
+            false,
+        )
+    }
+
+    /// A run's summary and its files, or one of the files
+    fn run(&self, path: &str) -> Response {
+        let Some(full) = resolve(&self.runs, path) else {
+            return Response::not_found();
+        };
+        if full.is_file() {
+            return fs::read(&full).map_or_else(
+                |_| Response::not_found(),
+                |contents| Response::ok("text/plain; charset=utf-8", contents),
+            );
+        }
+        if !full.join("manifest.json").exists() {
+            return Response::not_found();
+        }
+
+        let mut body = Vec::new();
+        let summary = fs::read_to_string(full.join("summary.txt")).unwrap_or_default();
+        body.push(format!("<pre>{}</pre>", escape(&summary)));
+        for (title, dir) in [
+            ("Crash reports", RunArtifacts::CRASHES_DIR),
+            ("Replays", RunArtifacts::REPLAYS_DIR),
+            ("Interesting games", RunArtifacts::INTERESTING_DIR),
+            ("Files", ""),
+        ] {
+            let files: Vec<_> = list(&full.join(dir))
+                .into_iter()
+                .filter(|f| full.join(dir).join(f).is_file())
+                .collect();
+            if files.is_empty() {
+                continue;
+            }
+            body.push(format!("<h2>{title}</h2><ul>"));
+            for file in files {
+                let link = escape(&Path::new(path).join(dir).join(&file).to_string_lossy());
+                body.push(format!(
+                    "<li><a href=\"/runs/{link}\">{}</a></li>",
+                    escape(&file)
+                ));
+            }
+            body.push("</ul>".into());
+        }
+
+        page(path, &body.concat(), false)
+    }
+}
+
+/// Serves the web UI on `addr`, showing the runs saved in `runs` and the statistics of `live`.
+/// With `queue`, runs can be queued from it for `daemon` to play
+pub fn serve(
+    addr: &str,
+    runs: PathBuf,
+    live: Option<Arc<Metrics>>,
+    queue: bool,
+) -> io::Result<thread::JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    let addr = listener.local_addr()?;
+    println!("Web UI available at http://{addr}");
+    let queue = if queue {
+        Some((Queue::new(&runs), std::env::current_dir()?))
+    } else {
+        None
+    };
+    let ui = WebUi {
+        runs,
+        live,
+        queue,
+        addr,
+        token: format!("{:032x}", rand::thread_rng().gen::<u128>()),
+    };
+    Ok(http::serve(listener, move |request| ui.handle(request)))
+}
@@ -0,0 +1,61 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Private working directory for a single game, removed once dropped.
+///
+/// Every entry of the current directory is linked into it (copied where symlinks aren't
+/// available), so the game finds the files it needs while anything new it writes stays private.
+pub struct WorkDir {
+    path: PathBuf,
+}
+
+impl WorkDir {
+    pub fn create(seed: u32) -> io::Result<Self> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!(
+            "eda-game-tester-{}-{seed}-{id}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&path)?;
+        let ret = Self { path };
+
+        let cwd = env::current_dir()?;
+        for entry in fs::read_dir(&cwd)? {
+            let entry = entry?;
+            link(&entry.path(), &ret.path.join(entry.file_name()))?;
+        }
+        Ok(ret)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(unix)]
+fn link(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(not(unix))]
+fn link(original: &Path, link: &Path) -> io::Result<()> {
+    if original.is_dir() {
+        std::os::windows::fs::symlink_dir(original, link)
+    } else {
+        fs::copy(original, link).map(|_| ())
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        // Doesn't follow the links, so the originals are left untouched
+        if let Err(err) = fs::remove_dir_all(&self.path) {
+            tracing::warn!(path = %self.path.display(), %err, "Couldn't remove the game directory");
+        }
+    }
+}